@@ -0,0 +1,121 @@
+//! cpu usage of subscriptions waiting at the head, measured side by side on the running app of this tree and of a baseline(linux).
+//!
+//! each app is started on a fresh RocksDB in a temp dir, and `TASKS` tendermint subscriptions are added by JSON-RPC
+//! to a local LCD mock whose latest height stays at 5. then the cpu time of the app process is read from /proc for `SECS` seconds.
+//!
+//! build the baseline(ex. the polling loop before async subscriptions) in a worktree, then run both:
+//! git worktree add ../ufc-baseline <commit> && (cd ../ufc-baseline && cargo build --release)
+//! cargo build --release && BASELINE_DIR=../ufc-baseline cargo run --release --example idle_tasks
+//! a dir has `config.toml`, `schema` and `target/release/ufc-appbase`. `TASKS`(default 50) and `SECS`(default 10) change the run.
+use std::env;
+use std::fs;
+use std::net::TcpListener;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::time;
+
+#[allow(dead_code)]
+#[path = "../src/libs/mock.rs"]
+mod mock;
+
+/// app startup and the subscriptions settle before the measurement.
+const SETTLE: Duration = Duration::from_secs(3);
+
+/// LCD whose latest height stays at 5.
+fn lcd_node() -> String {
+    mock::mock_json_node(|request| {
+        if request.path == "/blocks/latest" {
+            (200, json!({"block_id": {}, "block": {"header": {"height": "5"}}}))
+        } else {
+            (400, json!({"error": "requested block height is bigger then the chain length"}))
+        }
+    })
+}
+
+/// process cpu time from /proc/{pid}/stat(100 ticks per second)
+fn cpu_time(pid: u32) -> Duration {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap();
+    let fields: Vec<&str> = stat[stat.rfind(')').unwrap() + 2..].split_whitespace().collect();
+    let ticks = fields[11].parse::<u64>().unwrap() + fields[12].parse::<u64>().unwrap();
+    Duration::from_millis(ticks * 10)
+}
+
+fn env_u64(key: &str, default: u64) -> u64 {
+    env::var(key).ok().map_or(default, |value| { value.parse::<u64>().unwrap_or_else(|_| { panic!("{} is not u64! value={}", key, value) }) })
+}
+
+/// retries until the JSON-RPC server of the app is up.
+async fn subscribe(client: &reqwest::Client, url: &str, request: &Value) -> Value {
+    for _ in 0..100 {
+        if let Ok(res) = client.post(url).json(request).send().await {
+            return res.json::<Value>().await.unwrap();
+        }
+        time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("app does not answer! url={}", url);
+}
+
+/// cpu time of the app in `dir` over `secs` seconds with `tasks` idle subscriptions.
+async fn measure(dir: &Path, node: &str, tasks: u64, secs: u64) -> Duration {
+    let bin = dir.join("target/release/ufc-appbase");
+    // RocksDB is opened in the working dir, and the schema is read from it.
+    let work_dir = env::temp_dir().join(format!("ufc-idle-tasks-{}-{}", process::id(), dir.file_name().and_then(|name| { name.to_str() }).unwrap_or("app")));
+    fs::create_dir_all(&work_dir).unwrap();
+    symlink(dir.join("schema"), work_dir.join("schema")).unwrap();
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port().to_string();
+    let mut app = Command::new(&bin)
+        .current_dir(&work_dir)
+        .args(&["--config-dir", dir.to_str().unwrap(), "--jsonrpc-port", port.as_str()])
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap_or_else(|err| { panic!("app is not started! run cargo build --release in the dir. bin={}, error={}", bin.display(), err) });
+
+    let client = reqwest::Client::new();
+    let url = format!("http://127.0.0.1:{}", port);
+    for idx in 0..tasks {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tm_subscribe",
+            "params": {"target": "block", "sub_id": format!("idle-{}", idx), "start_height": 6, "nodes": [node]}
+        });
+        let response = subscribe(&client, url.as_str(), &request).await;
+        if response["result"].get("error").is_some() || response.get("error").is_some() {
+            panic!("subscription is rejected! response={}", response);
+        }
+    }
+
+    time::sleep(SETTLE).await;
+    let started_cpu = cpu_time(app.id());
+    time::sleep(Duration::from_secs(secs)).await;
+    let cpu = cpu_time(app.id()) - started_cpu;
+
+    let _ = app.kill();
+    let _ = app.wait();
+    let _ = fs::remove_dir_all(&work_dir);
+    cpu
+}
+
+fn report(name: &str, cpu: Duration, tasks: u64, secs: u64) {
+    let percent = cpu.as_secs_f64() / secs as f64 * 100.0;
+    println!("{}: {} idle tasks for {}s, cpu={:?}({:.2}%), per task={:.3}%", name, tasks, secs, cpu, percent, percent / tasks.max(1) as f64);
+}
+
+#[tokio::main]
+async fn main() {
+    let tasks = env_u64("TASKS", 50);
+    let secs = env_u64("SECS", 10);
+    let node = lcd_node();
+
+    let current = measure(Path::new(env!("CARGO_MANIFEST_DIR")), node.as_str(), tasks, secs).await;
+    report("current", current, tasks, secs);
+    if let Ok(baseline_dir) = env::var("BASELINE_DIR") {
+        let baseline = measure(PathBuf::from(baseline_dir).as_path(), node.as_str(), tasks, secs).await;
+        report("baseline", baseline, tasks, secs);
+        println!("current uses {:.1}% of the baseline cpu", current.as_secs_f64() / baseline.as_secs_f64().max(0.001) * 100.0);
+    }
+}
@@ -16,6 +16,7 @@ pub enum ExpectedError {
     ChannelError(String),
     FilterError(String),
     BlockHeightError(String),
+    ReorgError(String),
 }
 
 impl From<smtp::Error> for ExpectedError {
@@ -45,6 +46,12 @@ impl From<reqwest::Error> for ExpectedError {
     }
 }
 
+impl From<tokio_tungstenite::tungstenite::Error> for ExpectedError {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        ExpectedError::RequestError(err.to_string())
+    }
+}
+
 impl From<serde_json::Error> for ExpectedError {
     fn from(err: serde_json::Error) -> Self {
         ExpectedError::ParsingError(err.to_string())
@@ -75,6 +82,7 @@ impl Display for ExpectedError {
             ExpectedError::ChannelError(err) => write!(f, "{}", err),
             ExpectedError::FilterError(err) => write!(f, "{}", err),
             ExpectedError::BlockHeightError(err) => write!(f, "{}", err),
+            ExpectedError::ReorgError(err) => write!(f, "{}", err),
         }
     }
 }
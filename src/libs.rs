@@ -1,7 +1,16 @@
 pub mod serde;
+pub mod filter;
+pub mod path;
 pub mod mysql;
 pub mod rocks;
 pub mod mongo;
 pub mod opts;
 pub mod request;
 pub mod callback;
+pub mod token;
+pub mod cosmos;
+pub mod websocket;
+pub mod node;
+pub mod delivery;
+#[cfg(test)]
+pub mod mock;
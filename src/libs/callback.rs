@@ -1,46 +1,126 @@
 use appbase::channel;
 use appbase::channel::Sender;
-use serde_json::{Map, Value};
+use serde_json::{json, Value};
 
 use crate::error::error::ExpectedError;
 use crate::libs;
-use crate::libs::serde::select_value;
-use crate::plugin::mongo::MongoMsg;
-use crate::plugin::mysql::MySqlMsg;
+use crate::libs::path::Path;
+use crate::libs::serde::select_paths;
+use crate::plugin::mongo::{MongoMethod, MongoMsg};
+use crate::plugin::mysql::{CHECKPOINT_TABLE, MySqlMethod, MySqlMsg};
+use crate::plugin::rabbit::RabbitMsg;
 use crate::types::mysql::Schema;
 
-pub fn mysql(prefix: String, value: &Value, schema_opt: Option<&Schema>, mysql: &Sender) -> Result<(), ExpectedError> {
-    if libs::opts::bool(format!("{}-mysql-sync", prefix).as_str())? {
-        match schema_opt {
-            None => return Err(ExpectedError::NoneError(String::from("matched schema does not exist!"))),
-            Some(schema) => {
-                mysql_send(&mysql, schema, value.as_object().unwrap())?;
+/// rows of a block are written in a transaction with the height checkpoint of the task, even if there is no row.
+/// the sinks of a block are chosen by `enabled_sinks`.
+pub fn mysql(task_id: &str, height: u64, values: &[Value], schema_opt: Option<&Schema>, mysql: &Sender) -> Result<(), ExpectedError> {
+    match schema_opt {
+        None => Err(ExpectedError::NoneError(String::from("matched schema does not exist!"))),
+        Some(schema) => mysql_send(mysql, task_id, height, schema, values),
+    }
+}
+
+/// documents of a block. documents of an upsert schema are replaced by their natural key. ex) {"_id": {"height": "1"}, ...}
+pub fn mongo(task_id: &str, height: u64, values: &[Value], collection: &str, schema_opt: Option<&Schema>, mongo: &Sender) -> Result<(), ExpectedError> {
+    if !values.is_empty() {
+        let natural_key = schema_opt.and_then(|schema| { schema.natural_key() });
+        let documents: Vec<Value> = values.iter().map(|value| {
+            let mut document = value.as_object().unwrap().clone();
+            if let Some(natural_key) = natural_key.as_ref() {
+                let paths: Vec<(&str, &Path)> = natural_key.iter().map(|attribute| { (attribute.name.as_str(), &attribute.path) }).collect();
+                document.insert(String::from("_id"), Value::Object(select_paths(value.as_object().unwrap(), paths)));
             }
-        }
+            Value::Object(document)
+        }).collect();
+        let block = json!({
+            "task_id": task_id,
+            "height": height,
+            "upsert": natural_key.is_some(),
+            "documents": documents
+        });
+        let _ = mongo.send(MongoMsg::new(MongoMethod::Flush, String::from(task_id), String::from(collection), block))?;
     }
     Ok(())
 }
 
-pub fn mongo(prefix: String, value: &Value, collection: &str, mongo: &Sender) -> Result<(), ExpectedError> {
-    if libs::opts::bool(format!("{}-mongo-sync", prefix).as_str())? {
-        let mongo_msg = MongoMsg::new(String::from(collection), value.clone());
-        let _ = mongo.send(mongo_msg)?;
+/// messages of a block, acknowledged once every message is confirmed by the broker.
+pub fn rabbit(task_id: &str, height: u64, values: &[Value], rabbit: &Sender) -> Result<(), ExpectedError> {
+    if !values.is_empty() {
+        let messages: Vec<String> = values.iter().map(|value| { value.to_string() }).collect();
+        let _ = rabbit.send(RabbitMsg::new(json!(messages), json!({"task_id": task_id, "height": height})))?;
     }
     Ok(())
 }
 
-pub fn rabbit(prefix: String, value: &Value, rabbit: &Sender) -> Result<(), ExpectedError> {
+/// revert message of an orphaned block, which no height waits for.
+pub fn rabbit_revert(prefix: String, task_id: &str, revert: &Value, rabbit: &Sender) -> Result<(), ExpectedError> {
     if libs::opts::bool(format!("{}-rabbit-mq-publish", prefix).as_str())? {
-        let _ = rabbit.send(Value::String(value.to_string()))?;
+        let _ = rabbit.send(RabbitMsg::new(json!([revert.to_string()]), json!({"task_id": task_id})))?;
+    }
+    Ok(())
+}
+
+/// sinks enabled by the flags of the prefix, regardless of the order their plugins are initialized in.
+/// each of them has to accept a height sent to it. MySQL is sent every height for its checkpoint, and the others only heights having values.
+/// ex) "tendermint::block" with `--tm-block-mysql-sync` -> ["mysql"]
+pub fn enabled_sinks(prefix: &str, has_values: bool) -> Vec<String> {
+    let mut options = vec![("mysql", "mysql-sync")];
+    if has_values {
+        options.push(("mongo", "mongo-sync"));
+        options.push(("rabbit", "rabbit-mq-publish"));
+    }
+    options.into_iter().filter(|(_, option)| {
+        libs::opts::bool(format!("{}-{}", prefix, option).as_str()).unwrap_or(false)
+    }).map(|(sink, _)| { String::from(sink) }).collect()
+}
+
+/// deletes rows of an orphaned block. `task_id` keeps it in order with the writes of the task.
+pub fn mysql_revert(prefix: String, task_id: &str, table: &str, column: &str, block_hash: &str, mysql: &Sender) -> Result<(), ExpectedError> {
+    if libs::opts::bool(format!("{}-mysql-sync", prefix).as_str())? {
+        let query = format!("delete from {} where {}=:block_hash", table, column);
+        let mysql_msg = MySqlMsg::new(MySqlMethod::Execute, json!({"task_id": task_id, "query": query, "params": {"block_hash": block_hash}}));
+        let _ = mysql.send(mysql_msg)?;
+    }
+    Ok(())
+}
+
+/// moves the height checkpoint of the task back to the common ancestor, so the heights are written again.
+pub fn mysql_rewind(prefix: String, task_id: &str, height: u64, mysql: &Sender) -> Result<(), ExpectedError> {
+    if libs::opts::bool(format!("{}-mysql-sync", prefix).as_str())? {
+        let query = format!("update {} set height=:height where task_id=:task_id and height>:ancestor_height", CHECKPOINT_TABLE);
+        let params = json!({"height": height, "task_id": task_id, "ancestor_height": height});
+        let mysql_msg = MySqlMsg::new(MySqlMethod::Execute, json!({"task_id": task_id, "query": query, "params": params}));
+        let _ = mysql.send(mysql_msg)?;
+    }
+    Ok(())
+}
+
+/// deletes the height checkpoint of an unsubscribed task.
+pub fn mysql_checkpoint_delete(prefix: String, task_id: &str, mysql: &Sender) -> Result<(), ExpectedError> {
+    if libs::opts::bool(format!("{}-mysql-sync", prefix).as_str())? {
+        let query = format!("delete from {} where task_id=:task_id", CHECKPOINT_TABLE);
+        let mysql_msg = MySqlMsg::new(MySqlMethod::Execute, json!({"task_id": task_id, "query": query, "params": {"task_id": task_id}}));
+        let _ = mysql.send(mysql_msg)?;
+    }
+    Ok(())
+}
+
+/// deletes documents of an orphaned block.
+pub fn mongo_revert(prefix: String, task_id: &str, collection: &str, column: &str, block_hash: &str, mongo: &Sender) -> Result<(), ExpectedError> {
+    if libs::opts::bool(format!("{}-mongo-sync", prefix).as_str())? {
+        let mongo_msg = MongoMsg::new(MongoMethod::Delete, String::from(task_id), String::from(collection), json!({ column: block_hash }));
+        let _ = mongo.send(mongo_msg)?;
     }
     Ok(())
 }
 
-fn mysql_send(mysql_channel: &channel::Sender, schema: &Schema, values: &Map<String, Value>) -> Result<(), ExpectedError> {
-    let insert_query = schema.insert_query.clone();
-    let names: Vec<&str> = schema.attributes.iter().map(|attribute| { attribute.name.as_str() }).collect();
-    let selected_value = select_value(values, names)?;
-    let mysql_msg = MySqlMsg::new(insert_query, Value::Object(selected_value));
-    let _ = mysql_channel.send(mysql_msg)?;
+fn mysql_send(mysql_channel: &channel::Sender, task_id: &str, height: u64, schema: &Schema, values: &[Value]) -> Result<(), ExpectedError> {
+    let rows: Vec<Value> = values.iter().map(|value| { Value::Object(schema.row(value.as_object().unwrap())) }).collect();
+    let block = json!({
+        "task_id": task_id,
+        "height": height,
+        "inserts": [{"query": schema.insert_query, "rows": rows}]
+    });
+    let _ = mysql_channel.send(MySqlMsg::new(MySqlMethod::Flush, block))?;
     Ok(())
 }
\ No newline at end of file
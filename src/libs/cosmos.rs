@@ -0,0 +1,355 @@
+use serde_json::{json, Map, Value};
+
+use crate::error::error::ExpectedError;
+
+/*
+ * decodes protobuf tx bytes of CometBFT RPC into the JSON of Cosmos LCD(`tx_responses[].tx`).
+ * messages of common types(bank, staking, distribution, gov votes, authz exec and ibc transfer) are decoded by field,
+ * and the others keep their bytes as base64 `value`.
+ */
+enum Kind {
+    Str,
+    /// 64 bit integers are strings in the JSON of protobuf.
+    Uint,
+    /// 32 bit integers are numbers.
+    Uint32,
+    Bytes,
+    /// names by value. ex) (1, "SIGN_MODE_DIRECT")
+    Enum(&'static [(u64, &'static str)]),
+    Message(&'static [Field]),
+    Any,
+}
+
+enum Label {
+    Single,
+    Repeated,
+    /// a member of a oneof is left out unless it is set, like LCD.
+    Oneof,
+}
+
+struct Field {
+    number: u64,
+    name: &'static str,
+    kind: Kind,
+    label: Label,
+}
+
+const fn field(number: u64, name: &'static str, kind: Kind) -> Field {
+    Field { number, name, kind, label: Label::Single }
+}
+
+const fn repeated(number: u64, name: &'static str, kind: Kind) -> Field {
+    Field { number, name, kind, label: Label::Repeated }
+}
+
+const fn oneof(number: u64, name: &'static str, kind: Kind) -> Field {
+    Field { number, name, kind, label: Label::Oneof }
+}
+
+const COIN: &[Field] = &[field(1, "denom", Kind::Str), field(2, "amount", Kind::Str)];
+const HEIGHT: &[Field] = &[field(1, "revision_number", Kind::Uint), field(2, "revision_height", Kind::Uint)];
+
+const TX_RAW: &[Field] = &[field(1, "body", Kind::Message(TX_BODY)), field(2, "auth_info", Kind::Message(AUTH_INFO)), repeated(3, "signatures", Kind::Bytes)];
+const TX_BODY: &[Field] = &[
+    repeated(1, "messages", Kind::Any),
+    field(2, "memo", Kind::Str),
+    field(3, "timeout_height", Kind::Uint),
+    repeated(1023, "extension_options", Kind::Any),
+    repeated(2047, "non_critical_extension_options", Kind::Any),
+];
+const AUTH_INFO: &[Field] = &[repeated(1, "signer_infos", Kind::Message(SIGNER_INFO)), field(2, "fee", Kind::Message(FEE))];
+const SIGNER_INFO: &[Field] = &[field(1, "public_key", Kind::Any), field(2, "mode_info", Kind::Message(&MODE_INFO)), field(3, "sequence", Kind::Uint)];
+const FEE: &[Field] = &[repeated(1, "amount", Kind::Message(COIN)), field(2, "gas_limit", Kind::Uint), field(3, "payer", Kind::Str), field(4, "granter", Kind::Str)];
+
+/// statics, as the mode info of a multisig nests the mode infos of its keys.
+static MODE_INFO: [Field; 2] = [oneof(1, "single", Kind::Message(MODE_INFO_SINGLE)), oneof(2, "multi", Kind::Message(&MODE_INFO_MULTI))];
+static MODE_INFO_MULTI: [Field; 2] = [field(1, "bitarray", Kind::Message(COMPACT_BIT_ARRAY)), repeated(2, "mode_infos", Kind::Message(&MODE_INFO))];
+const MODE_INFO_SINGLE: &[Field] = &[field(1, "mode", Kind::Enum(SIGN_MODE))];
+const COMPACT_BIT_ARRAY: &[Field] = &[field(1, "extra_bits_stored", Kind::Uint32), field(2, "elems", Kind::Bytes)];
+const SIGN_MODE: &[(u64, &str)] = &[
+    (0, "SIGN_MODE_UNSPECIFIED"),
+    (1, "SIGN_MODE_DIRECT"),
+    (2, "SIGN_MODE_TEXTUAL"),
+    (3, "SIGN_MODE_DIRECT_AUX"),
+    (127, "SIGN_MODE_LEGACY_AMINO_JSON"),
+    (191, "SIGN_MODE_EIP_191"),
+];
+
+const PUB_KEY: &[Field] = &[field(1, "key", Kind::Bytes)];
+const MSG_SEND: &[Field] = &[field(1, "from_address", Kind::Str), field(2, "to_address", Kind::Str), repeated(3, "amount", Kind::Message(COIN))];
+/// `Input` and `Output` of MsgMultiSend.
+const BANK_IO: &[Field] = &[field(1, "address", Kind::Str), repeated(2, "coins", Kind::Message(COIN))];
+const MSG_MULTI_SEND: &[Field] = &[repeated(1, "inputs", Kind::Message(BANK_IO)), repeated(2, "outputs", Kind::Message(BANK_IO))];
+const MSG_DELEGATE: &[Field] = &[field(1, "delegator_address", Kind::Str), field(2, "validator_address", Kind::Str), field(3, "amount", Kind::Message(COIN))];
+const MSG_BEGIN_REDELEGATE: &[Field] = &[
+    field(1, "delegator_address", Kind::Str),
+    field(2, "validator_src_address", Kind::Str),
+    field(3, "validator_dst_address", Kind::Str),
+    field(4, "amount", Kind::Message(COIN)),
+];
+const MSG_WITHDRAW_DELEGATOR_REWARD: &[Field] = &[field(1, "delegator_address", Kind::Str), field(2, "validator_address", Kind::Str)];
+const MSG_VOTE: &[Field] = &[field(1, "proposal_id", Kind::Uint), field(2, "voter", Kind::Str), field(3, "option", Kind::Enum(VOTE_OPTION))];
+const MSG_VOTE_V1: &[Field] = &[
+    field(1, "proposal_id", Kind::Uint),
+    field(2, "voter", Kind::Str),
+    field(3, "option", Kind::Enum(VOTE_OPTION)),
+    field(4, "metadata", Kind::Str),
+];
+const VOTE_OPTION: &[(u64, &str)] = &[
+    (0, "VOTE_OPTION_UNSPECIFIED"),
+    (1, "VOTE_OPTION_YES"),
+    (2, "VOTE_OPTION_ABSTAIN"),
+    (3, "VOTE_OPTION_NO"),
+    (4, "VOTE_OPTION_NO_WITH_VETO"),
+];
+const MSG_EXEC: &[Field] = &[field(1, "grantee", Kind::Str), repeated(2, "msgs", Kind::Any)];
+const MSG_TRANSFER: &[Field] = &[
+    field(1, "source_port", Kind::Str),
+    field(2, "source_channel", Kind::Str),
+    field(3, "token", Kind::Message(COIN)),
+    field(4, "sender", Kind::Str),
+    field(5, "receiver", Kind::Str),
+    field(6, "timeout_height", Kind::Message(HEIGHT)),
+    field(7, "timeout_timestamp", Kind::Uint),
+];
+
+fn known_type(type_url: &str) -> Option<&'static [Field]> {
+    let fields = match type_url {
+        "/cosmos.crypto.secp256k1.PubKey" | "/cosmos.crypto.ed25519.PubKey" => PUB_KEY,
+        "/cosmos.bank.v1beta1.MsgSend" => MSG_SEND,
+        "/cosmos.bank.v1beta1.MsgMultiSend" => MSG_MULTI_SEND,
+        "/cosmos.staking.v1beta1.MsgDelegate" | "/cosmos.staking.v1beta1.MsgUndelegate" => MSG_DELEGATE,
+        "/cosmos.staking.v1beta1.MsgBeginRedelegate" => MSG_BEGIN_REDELEGATE,
+        "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward" => MSG_WITHDRAW_DELEGATOR_REWARD,
+        "/cosmos.gov.v1beta1.MsgVote" => MSG_VOTE,
+        "/cosmos.gov.v1.MsgVote" => MSG_VOTE_V1,
+        "/cosmos.authz.v1beta1.MsgExec" => MSG_EXEC,
+        "/ibc.applications.transfer.v1.MsgTransfer" => MSG_TRANSFER,
+        _ => return None,
+    };
+    Some(fields)
+}
+
+/// base64 tx of `/block` or `/tx_search`.
+pub fn decode_tx(tx: &str) -> Result<Value, ExpectedError> {
+    let bytes = match base64::decode(tx) {
+        Ok(bytes) => bytes,
+        Err(err) => return Err(ExpectedError::ParsingError(format!("tx is not base64! error={}", err))),
+    };
+    let mut decoded = Map::new();
+    decoded.insert(String::from("@type"), json!("/cosmos.tx.v1beta1.Tx"));
+    decoded.extend(decode_message(&bytes, TX_RAW)?);
+    Ok(Value::Object(decoded))
+}
+
+/// unset fields have the default value like LCD.
+fn decode_message(bytes: &[u8], fields: &[Field]) -> Result<Map<String, Value>, ExpectedError> {
+    let mut decoded = Map::new();
+    for field in fields.iter() {
+        let default = match (&field.kind, &field.label) {
+            (_, Label::Oneof) => continue,
+            (_, Label::Repeated) => json!([]),
+            (Kind::Str, _) | (Kind::Bytes, _) => json!(""),
+            (Kind::Uint, _) => json!("0"),
+            (Kind::Uint32, _) => json!(0),
+            (Kind::Enum(names), _) => enum_name(names, 0),
+            _ => Value::Null,
+        };
+        decoded.insert(String::from(field.name), default);
+    }
+    for (number, wire) in read_fields(bytes)?.into_iter() {
+        let field = match fields.iter().find(|field| { field.number == number }) {
+            Some(field) => field,
+            None => continue,
+        };
+        let value = match (&field.kind, wire) {
+            (Kind::Uint, Wire::Varint(varint)) => Value::String(varint.to_string()),
+            (Kind::Uint32, Wire::Varint(varint)) => json!(varint),
+            (Kind::Enum(names), Wire::Varint(varint)) => enum_name(names, varint),
+            (Kind::Str, Wire::Bytes(bytes)) => Value::String(String::from_utf8_lossy(bytes).into_owned()),
+            (Kind::Bytes, Wire::Bytes(bytes)) => Value::String(base64::encode(bytes)),
+            (Kind::Message(fields), Wire::Bytes(bytes)) => Value::Object(decode_message(bytes, fields)?),
+            (Kind::Any, Wire::Bytes(bytes)) => decode_any(bytes)?,
+            _ => return Err(ExpectedError::ParsingError(format!("wire type of {} is invalid!", field.name))),
+        };
+        match decoded.get_mut(field.name) {
+            Some(Value::Array(values)) if matches!(field.label, Label::Repeated) => values.push(value),
+            _ => {
+                decoded.insert(String::from(field.name), value);
+            }
+        }
+    }
+    Ok(decoded)
+}
+
+/// an unknown value is the number, like the JSON of protobuf.
+fn enum_name(names: &[(u64, &str)], value: u64) -> Value {
+    match names.iter().find(|(number, _)| { *number == value }) {
+        Some((_, name)) => json!(name),
+        None => json!(value),
+    }
+}
+
+/// `google.protobuf.Any` as `{"@type": type_url, ...fields}`.
+fn decode_any(bytes: &[u8]) -> Result<Value, ExpectedError> {
+    let mut type_url = String::new();
+    let mut value: &[u8] = &[];
+    for (number, wire) in read_fields(bytes)?.into_iter() {
+        match (number, wire) {
+            (1, Wire::Bytes(bytes)) => type_url = String::from_utf8_lossy(bytes).into_owned(),
+            (2, Wire::Bytes(bytes)) => value = bytes,
+            _ => {}
+        }
+    }
+    let mut decoded = Map::new();
+    decoded.insert(String::from("@type"), Value::String(type_url.clone()));
+    match known_type(type_url.as_str()) {
+        Some(fields) => decoded.extend(decode_message(value, fields)?),
+        None => {
+            decoded.insert(String::from("value"), Value::String(base64::encode(value)));
+        }
+    }
+    Ok(Value::Object(decoded))
+}
+
+enum Wire<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+fn read_fields(bytes: &[u8]) -> Result<Vec<(u64, Wire)>, ExpectedError> {
+    let mut fields: Vec<(u64, Wire)> = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let key = read_varint(bytes, &mut idx)?;
+        let wire = match key & 0x7 {
+            0 => Wire::Varint(read_varint(bytes, &mut idx)?),
+            2 => {
+                let len = read_varint(bytes, &mut idx)? as usize;
+                if len > bytes.len() - idx {
+                    return Err(ExpectedError::ParsingError(String::from("length of protobuf field exceeds the message!")));
+                }
+                idx += len;
+                Wire::Bytes(&bytes[idx - len..idx])
+            }
+            1 => {
+                idx += 8;
+                Wire::Fixed
+            }
+            5 => {
+                idx += 4;
+                Wire::Fixed
+            }
+            wire_type => return Err(ExpectedError::ParsingError(format!("unsupported protobuf wire type! wire_type={}", wire_type))),
+        };
+        fields.push((key >> 3, wire));
+    }
+    if idx > bytes.len() {
+        return Err(ExpectedError::ParsingError(String::from("protobuf message ends in the middle of a field!")));
+    }
+    Ok(fields)
+}
+
+fn read_varint(bytes: &[u8], idx: &mut usize) -> Result<u64, ExpectedError> {
+    let mut varint: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = match bytes.get(*idx) {
+            Some(byte) => *byte,
+            None => return Err(ExpectedError::ParsingError(String::from("protobuf varint is not terminated!"))),
+        };
+        *idx += 1;
+        varint |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(varint);
+        }
+    }
+    Err(ExpectedError::ParsingError(String::from("protobuf varint is too long!")))
+}
+
+#[cfg(test)]
+pub(crate) mod cosmos_test {
+    use serde_json::json;
+
+    use crate::libs::cosmos::decode_tx;
+
+    /// MsgSend of 1000uatom from cosmos1from to cosmos1to with memo "ufc", fee 500uatom, gas 200000, sequence 7 and a secp256k1 key.
+    pub(crate) const SEND_TX: &str = "Ck4KRwocL2Nvc21vcy5iYW5rLnYxYmV0YTEuTXNnU2VuZBInCgtjb3Ntb3MxZnJvbRIJY29zbW9zMXRvGg0KBXVhdG9tEgQxMDAwEgN1ZmMSSAoyCigKHy9jb3Ntb3MuY3J5cHRvLnNlY3AyNTZrMS5QdWJLZXkSBQoDAQIDEgQKAggBGAcSEgoMCgV1YXRvbRIDNTAwEMCaDBoDBAUG";
+    pub(crate) const SEND_TX_HASH: &str = "9B65925FF2B56141570AC76B6192F47FD3A87ACCEF837A345C565312D0CFA838";
+    /// MsgExec of a yes vote on proposal 12 and MsgMultiSend of 2000uatom to two outputs, signed by a 2 of 2 multisig.
+    const MULTISIG_TX: &str = "CuYBCmQKHS9jb3Ntb3MuYXV0aHoudjFiZXRhMS5Nc2dFeGVjEkMKDmNvc21vczFncmFudGVlEjEKGy9jb3Ntb3MuZ292LnYxYmV0YTEuTXNnVm90ZRISCAwSDGNvc21vczF2b3RlchgBCn4KIS9jb3Ntb3MuYmFuay52MWJldGExLk1zZ011bHRpU2VuZBJZChwKC2Nvc21vczFmcm9tEg0KBXVhdG9tEgQyMDAwEhoKCWNvc21vczF0bxINCgV1YXRvbRIEMTAwMBIdCgxjb3Ntb3Mxb3RoZXISDQoFdWF0b20SBDEwMDASLwoZEhUSEwoFCAISAcASBAoCCAESBAoCCH8YAxISCgwKBXVhdG9tEgM1MDAQwJoMGgMEBQYaAwcICQ==";
+    /// a message of unknown type keeps its bytes.
+    const UNKNOWN_TX: &str = "CjUKMwoqL29zbW9zaXMuZ2FtbS52MWJldGExLk1zZ1N3YXBFeGFjdEFtb3VudEluEgUKA2FiYw==";
+
+    #[test]
+    fn decode_tx_test() {
+        let tx = decode_tx(SEND_TX).unwrap();
+        assert_eq!(tx, json!({
+            "@type": "/cosmos.tx.v1beta1.Tx",
+            "body": {
+                "messages": [{
+                    "@type": "/cosmos.bank.v1beta1.MsgSend",
+                    "from_address": "cosmos1from",
+                    "to_address": "cosmos1to",
+                    "amount": [{"denom": "uatom", "amount": "1000"}]
+                }],
+                "memo": "ufc",
+                "timeout_height": "0",
+                "extension_options": [],
+                "non_critical_extension_options": []
+            },
+            "auth_info": {
+                "signer_infos": [{
+                    "public_key": {"@type": "/cosmos.crypto.secp256k1.PubKey", "key": "AQID"},
+                    "mode_info": {"single": {"mode": "SIGN_MODE_DIRECT"}},
+                    "sequence": "7"
+                }],
+                "fee": {"amount": [{"denom": "uatom", "amount": "500"}], "gas_limit": "200000", "payer": "", "granter": ""}
+            },
+            "signatures": ["BAUG"]
+        }));
+    }
+
+    #[test]
+    fn decode_tx_multisig_test() {
+        let tx = decode_tx(MULTISIG_TX).unwrap();
+        assert_eq!(tx["body"]["messages"], json!([
+            {
+                "@type": "/cosmos.authz.v1beta1.MsgExec",
+                "grantee": "cosmos1grantee",
+                "msgs": [{"@type": "/cosmos.gov.v1beta1.MsgVote", "proposal_id": "12", "voter": "cosmos1voter", "option": "VOTE_OPTION_YES"}]
+            },
+            {
+                "@type": "/cosmos.bank.v1beta1.MsgMultiSend",
+                "inputs": [{"address": "cosmos1from", "coins": [{"denom": "uatom", "amount": "2000"}]}],
+                "outputs": [
+                    {"address": "cosmos1to", "coins": [{"denom": "uatom", "amount": "1000"}]},
+                    {"address": "cosmos1other", "coins": [{"denom": "uatom", "amount": "1000"}]}
+                ]
+            }
+        ]));
+        assert_eq!(tx["auth_info"]["signer_infos"][0], json!({
+            "public_key": null,
+            "mode_info": {
+                "multi": {
+                    "bitarray": {"extra_bits_stored": 2, "elems": "wA=="},
+                    "mode_infos": [{"single": {"mode": "SIGN_MODE_DIRECT"}}, {"single": {"mode": "SIGN_MODE_LEGACY_AMINO_JSON"}}]
+                }
+            },
+            "sequence": "3"
+        }));
+        assert_eq!(tx["signatures"], json!(["BAUG", "BwgJ"]));
+    }
+
+    #[test]
+    fn decode_tx_unknown_message_test() {
+        let tx = decode_tx(UNKNOWN_TX).unwrap();
+        assert_eq!(tx["body"]["messages"][0], json!({"@type": "/osmosis.gamm.v1beta1.MsgSwapExactAmountIn", "value": "CgNhYmM="}));
+    }
+
+    #[test]
+    fn decode_tx_invalid_test() {
+        assert!(decode_tx("not base64!").is_err());
+        assert!(decode_tx("CgUKAwoB").is_err());
+    }
+}
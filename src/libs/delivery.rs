@@ -0,0 +1,180 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use appbase::channel;
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value};
+
+use crate::libs::serde::{get_str, get_u64};
+use crate::plugin::rocks::{RocksMethod, RocksMsg};
+use crate::types::subscribe::timestamp;
+
+/*
+ * heights sent to the sinks but not yet accepted by all of them, by task id.
+ * a sink acknowledges a height once it is written for good, and the progress of a task is kept in RocksDB
+ * only up to the lowest height still pending, so a restart sends the undelivered heights again.
+ */
+/// heights a subscription may run ahead of its sinks before it waits for them.
+pub const MAX_PENDING_HEIGHTS: usize = 1000;
+/// retries of a sink write failing on a transient error.
+pub const DEFAULT_RETRIES: u32 = 5;
+/// first retry delay, doubled on every retry up to 2^MAX_RETRY_SHIFT times.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_SHIFT: u32 = 6;
+const DEAD_LETTER_PREFIX: &str = "dead_letter";
+
+#[derive(Default)]
+struct Pending {
+    /// sinks yet to accept each height.
+    heights: BTreeMap<u64, BTreeSet<String>>,
+    /// true if a height is delivered since the last `take_progress`.
+    progressed: bool,
+}
+
+static PENDING: Lazy<Mutex<HashMap<String, Pending>>> = Lazy::new(|| { Mutex::new(HashMap::new()) });
+
+/// waits for the sinks to accept the height. ex) ["mysql", "rabbit"]
+/// a height sent again, ex) after a chain reorganization, waits for the given sinks only.
+pub fn expect(task_id: &str, height: u64, sinks: Vec<String>) {
+    if sinks.is_empty() {
+        return;
+    }
+    let mut pending = PENDING.lock().unwrap();
+    pending.entry(String::from(task_id)).or_default().heights.insert(height, sinks.into_iter().collect());
+}
+
+/// the sink accepted the height. a height not waited for is ignored.
+pub fn ack(task_id: &str, height: u64, sink: &str) {
+    let mut pending = PENDING.lock().unwrap();
+    if let Some(task) = pending.get_mut(task_id) {
+        if let Some(sinks) = task.heights.get_mut(&height) {
+            sinks.remove(sink);
+            if sinks.is_empty() {
+                task.heights.remove(&height);
+                task.progressed = true;
+            }
+        }
+    }
+}
+
+/// acknowledges the `delivery` of a sink message. ex) {"task_id": "task:ethereum:block:mainnet", "height": 1}
+/// a delivery of no height(ex. revert) is ignored.
+pub fn ack_value(delivery: &Value, sink: &str) {
+    if let Some(delivery) = delivery.as_object() {
+        if let (Ok(task_id), Ok(height)) = (get_str(delivery, "task_id"), get_u64(delivery, "height")) {
+            ack(task_id, height, sink);
+        }
+    }
+}
+
+/// height to resume from: the lowest height still pending, or `curr_height` if every sent height is delivered.
+pub fn resume_height(task_id: &str, curr_height: u64) -> u64 {
+    let pending = PENDING.lock().unwrap();
+    match pending.get(task_id).and_then(|task| { task.heights.keys().next() }) {
+        Some(height) => curr_height.min(*height),
+        None => curr_height,
+    }
+}
+
+pub fn pending(task_id: &str) -> usize {
+    let pending = PENDING.lock().unwrap();
+    pending.get(task_id).map_or(0, |task| { task.heights.len() })
+}
+
+/// true once after a height of the task is delivered, so the progress is saved again.
+pub fn take_progress(task_id: &str) -> bool {
+    let mut pending = PENDING.lock().unwrap();
+    pending.get_mut(task_id).map_or(false, |task| { std::mem::replace(&mut task.progressed, false) })
+}
+
+/// forgets the pending heights. ex) unsubscribed, or resubscribed to send them again
+pub fn clear(task_id: &str) {
+    PENDING.lock().unwrap().remove(task_id);
+}
+
+/// delay before the retry of the attempt. ex) 0 -> 1s, 1 -> 2s, 6 or more -> 64s
+pub fn retry_delay(attempt: u32) -> Duration {
+    RETRY_DELAY * (1 << attempt.min(MAX_RETRY_SHIFT))
+}
+
+/// prefix of the dead letters of the sink, or of a task. ex) dead_letter:mongo:task:ethereum:block:mainnet:
+pub fn dead_letter_prefix(sink: &str, task_id: Option<&str>) -> String {
+    match task_id {
+        None => format!("{}:{}:", DEAD_LETTER_PREFIX, sink),
+        Some(task_id) => format!("{}:{}:{}:", DEAD_LETTER_PREFIX, sink, task_id),
+    }
+}
+
+/// the time in nanoseconds keeps the letters of every attempt at a height apart.
+/// ex) dead_letter:mongo:task:ethereum:block:mainnet:10:1628000000000000000:0
+fn dead_letter_key(sink: &str, task_id: &str, height: u64, idx: usize) -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| { duration.as_nanos() });
+    format!("{}{}:{}:{}", dead_letter_prefix(sink, Some(task_id)), height, nanos, idx)
+}
+
+/// keeps a value the sink failed to write for good in RocksDB, with the error. ex) {"document": {...}}
+pub fn dead_letter(rocks_channel: &channel::Sender, sink: &str, task_id: &str, height: u64, idx: usize, mut letter: Map<String, Value>, error: &str) {
+    println!("{} value moved to dead letters! task_id={}, height={}, error={}", sink, task_id, height, error);
+    letter.insert(String::from("task_id"), Value::from(task_id));
+    letter.insert(String::from("height"), Value::from(height));
+    letter.insert(String::from("error"), Value::from(error));
+    letter.insert(String::from("created_at"), Value::from(timestamp()));
+    let key = dead_letter_key(sink, task_id, height, idx);
+    let _ = rocks_channel.send(RocksMsg::new(RocksMethod::Put, key, Value::String(Value::Object(letter).to_string())));
+}
+
+#[cfg(test)]
+mod delivery_test {
+    use serde_json::{json, Value};
+
+    use std::time::Duration;
+
+    use crate::libs::delivery::{ack, ack_value, clear, dead_letter_key, dead_letter_prefix, expect, pending, resume_height, retry_delay, take_progress};
+
+    #[test]
+    fn delivery_test() {
+        let task_id = "task:mock:block:delivery";
+        expect(task_id, 1, vec![String::from("mysql"), String::from("rabbit")]);
+        expect(task_id, 2, vec![String::from("mysql"), String::from("rabbit")]);
+        assert_eq!(resume_height(task_id, 3), 1);
+
+        ack(task_id, 2, "mysql");
+        ack(task_id, 2, "rabbit");
+        assert!(take_progress(task_id));
+        assert!(!take_progress(task_id));
+        assert_eq!((pending(task_id), resume_height(task_id, 3)), (1, 1));
+
+        ack(task_id, 1, "mysql");
+        ack_value(&json!({"task_id": task_id, "height": 1}), "rabbit");
+        ack_value(&Value::Null, "rabbit");
+        assert_eq!((pending(task_id), resume_height(task_id, 3)), (0, 3));
+        clear(task_id);
+    }
+
+    #[test]
+    fn delivery_without_sinks_test() {
+        let task_id = "task:mock:block:no_sinks";
+        expect(task_id, 1, Vec::new());
+        assert_eq!((pending(task_id), resume_height(task_id, 2)), (0, 2));
+        clear(task_id);
+    }
+
+    #[test]
+    fn retry_delay_test() {
+        assert_eq!(retry_delay(0), Duration::from_secs(1));
+        assert_eq!(retry_delay(3), Duration::from_secs(8));
+        assert_eq!(retry_delay(10), Duration::from_secs(64));
+    }
+
+    #[test]
+    fn dead_letter_key_test() {
+        let task_id = "task:ethereum:block:mainnet";
+        let prefix = dead_letter_prefix("mongo", Some(task_id));
+        assert_eq!(prefix, "dead_letter:mongo:task:ethereum:block:mainnet:");
+        assert!(prefix.starts_with(dead_letter_prefix("mongo", None).as_str()));
+
+        let key = dead_letter_key("mongo", task_id, 10, 0);
+        assert!(key.starts_with(format!("{}10:", prefix).as_str()) && key.ends_with(":0"));
+    }
+}
@@ -0,0 +1,582 @@
+use std::cmp::Ordering;
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::error::error::ExpectedError;
+use crate::libs::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(Operator),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Comma,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Match,
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Path, Operator, String),
+    In(Path, Vec<String>),
+    Match(Path, Regex),
+    Exists(Path),
+}
+
+/// compiled filter condition of a subscription.
+/// ex) `status=0x1 & (value>=0xde0b6b3a7640000 | to in (0xa9d1..., 0x28c6...)) & !exists(contractAddress)`
+#[derive(Debug, Clone)]
+pub enum Filter {
+    /// empty filter matching every value
+    All,
+    Expr(Expr),
+    /// filter which failed to compile. matching on it returns the error.
+    Invalid(String),
+}
+
+impl Filter {
+    /// errors point at the column(1-based) of the token which failed.
+    /// bare keys are found only at the top level if `strict`.
+    pub fn parse(filter: &str, strict: bool) -> Result<Self, ExpectedError> {
+        let tokens = tokenize(filter)?;
+        if tokens.is_empty() {
+            return Ok(Filter::All);
+        }
+        let mut parser = Parser { tokens, pos: 0, end: filter.chars().count() + 1, strict };
+        let expr = parser.parse_or()?;
+        match parser.next() {
+            None => Ok(Filter::Expr(expr)),
+            token => Err(parser.error("'&', '|' or end of filter", token)),
+        }
+    }
+
+    /// same as `parse`, but keeps the error to be returned on matching.
+    pub fn compile(filter: &str, strict: bool) -> Self {
+        match Self::parse(filter, strict) {
+            Ok(filter) => filter,
+            Err(err) => Filter::Invalid(err.to_string()),
+        }
+    }
+
+    pub fn matches(&self, values: &Map<String, Value>) -> Result<bool, ExpectedError> {
+        Ok(self.evaluate(values)?.0)
+    }
+
+    /// result and the paths of the values whose conditions made it true. ex) `receipt.status`, `logs[0].address`
+    pub fn evaluate(&self, values: &Map<String, Value>) -> Result<(bool, Vec<String>), ExpectedError> {
+        match self {
+            Filter::All => Ok((true, Vec::new())),
+            Filter::Expr(expr) => {
+                let mut paths: Vec<String> = Vec::new();
+                let result = expr.eval(values, &mut paths);
+                Ok((result, paths))
+            }
+            Filter::Invalid(err) => Err(ExpectedError::InvalidError(err.clone())),
+        }
+    }
+}
+
+impl Expr {
+    /// paths of conditions under a false or negated expression are dropped.
+    fn eval(&self, values: &Map<String, Value>, paths: &mut Vec<String>) -> bool {
+        let len = paths.len();
+        let result = match self {
+            Expr::And(left, right) => left.eval(values, paths) && right.eval(values, paths),
+            Expr::Or(left, right) => left.eval(values, paths) || right.eval(values, paths),
+            Expr::Not(expr) => {
+                let result = !expr.eval(values, paths);
+                paths.truncate(len);
+                result
+            }
+            _ => {
+                let resolved = self.path().resolve(values);
+                if resolved.is_empty() {
+                    self.eval_condition(&Value::Null)
+                } else {
+                    let mut result = false;
+                    for (path, found) in resolved.into_iter() {
+                        match found {
+                            Value::Array(items) if !matches!(self, Expr::Exists(_)) => {
+                                for (idx, item) in items.iter().enumerate() {
+                                    if self.eval_condition(item) {
+                                        paths.push(format!("{}[{}]", path, idx));
+                                        result = true;
+                                    }
+                                }
+                            }
+                            _ => {
+                                if self.eval_condition(found) {
+                                    paths.push(path);
+                                    result = true;
+                                }
+                            }
+                        }
+                    }
+                    result
+                }
+            }
+        };
+        if !result {
+            paths.truncate(len);
+        }
+        result
+    }
+
+    /// path of a condition. a condition is true if any value of the path, or any element of an array value, meets it.
+    fn path(&self) -> &Path {
+        match self {
+            Expr::Compare(path, _, _) | Expr::In(path, _) | Expr::Match(path, _) | Expr::Exists(path) => path,
+            _ => unreachable!(),
+        }
+    }
+
+    fn eval_condition(&self, found: &Value) -> bool {
+        match self {
+            Expr::Compare(_, operator, value) => match operator {
+                Operator::Eq => equals(found, value),
+                Operator::Ne => !equals(found, value),
+                _ => match compare(found, value) {
+                    Some(ordering) => match operator {
+                        Operator::Gt => ordering == Ordering::Greater,
+                        Operator::Ge => ordering != Ordering::Less,
+                        Operator::Lt => ordering == Ordering::Less,
+                        _ => ordering != Ordering::Greater,
+                    },
+                    None => false,
+                },
+            },
+            Expr::In(_, items) => items.iter().any(|item| { equals(found, item) }),
+            Expr::Match(_, regex) => !found.is_null() && regex.is_match(to_text(found).as_str()),
+            Expr::Exists(_) => !found.is_null(),
+            _ => false,
+        }
+    }
+}
+
+/// tokens with their columns(1-based).
+fn tokenize(filter: &str) -> Result<Vec<(Token, usize)>, ExpectedError> {
+    let mut tokens: Vec<(Token, usize)> = Vec::new();
+    let chars: Vec<char> = filter.chars().collect();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let c = chars[idx];
+        let column = idx + 1;
+        idx += 1;
+        let next_is_eq = chars.get(idx) == Some(&'=');
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            '&' => Token::And,
+            '|' => Token::Or,
+            '~' => Token::Op(Operator::Match),
+            '=' | '!' | '>' | '<' => {
+                if next_is_eq {
+                    idx += 1;
+                }
+                match (c, next_is_eq) {
+                    ('=', _) => Token::Op(Operator::Eq),
+                    ('!', true) => Token::Op(Operator::Ne),
+                    ('!', false) => Token::Not,
+                    ('>', true) => Token::Op(Operator::Ge),
+                    ('>', false) => Token::Op(Operator::Gt),
+                    ('<', true) => Token::Op(Operator::Le),
+                    _ => Token::Op(Operator::Lt),
+                }
+            }
+            '"' | '\'' => {
+                let mut quoted = String::new();
+                loop {
+                    match chars.get(idx) {
+                        Some('\\') if idx + 1 < chars.len() => {
+                            quoted.push(chars[idx + 1]);
+                            idx += 2;
+                        }
+                        Some(next) if *next == c => {
+                            idx += 1;
+                            break;
+                        }
+                        Some(next) if *next != '\\' => {
+                            quoted.push(*next);
+                            idx += 1;
+                        }
+                        _ => return Err(ExpectedError::InvalidError(format!("invalid filter! quote at column {} is not closed!", column))),
+                    }
+                }
+                Token::Quoted(quoted)
+            }
+            _ => {
+                let mut word = String::from(c);
+                while let Some(next) = chars.get(idx) {
+                    if next.is_whitespace() || "()&|,!=<>~\"'".contains(*next) {
+                        break;
+                    }
+                    // brackets of a path(ex. `['@type']`) belong to the word
+                    if *next == '[' {
+                        while let Some(inner) = chars.get(idx) {
+                            word.push(*inner);
+                            idx += 1;
+                            if *inner == ']' {
+                                break;
+                            }
+                        }
+                        continue;
+                    }
+                    word.push(*next);
+                    idx += 1;
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push((token, column));
+    }
+    Ok(tokens)
+}
+
+fn describe(token: &Option<(Token, usize)>) -> String {
+    let token = match token {
+        Some((token, _)) => token,
+        None => return String::from("end of filter"),
+    };
+    match token {
+        Token::Word(word) => format!("'{}'", word),
+        Token::Quoted(quoted) => format!("\"{}\"", quoted),
+        Token::Op(operator) => String::from(match operator {
+            Operator::Eq => "'='",
+            Operator::Ne => "'!='",
+            Operator::Gt => "'>'",
+            Operator::Ge => "'>='",
+            Operator::Lt => "'<'",
+            Operator::Le => "'<='",
+            Operator::Match => "'~'",
+        }),
+        Token::And => String::from("'&'"),
+        Token::Or => String::from("'|'"),
+        Token::Not => String::from("'!'"),
+        Token::Open => String::from("'('"),
+        Token::Close => String::from("')'"),
+        Token::Comma => String::from("','"),
+    }
+}
+
+/// recursive descent parser. `|` binds weaker than `&`, and `!` binds the nearest condition.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// column reported when the filter ends too early
+    end: usize,
+    strict: bool,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| { token })
+    }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn error(&self, expected: &str, token: Option<(Token, usize)>) -> ExpectedError {
+        let column = token.as_ref().map_or(self.end, |(_, column)| { *column });
+        ExpectedError::InvalidError(format!("invalid filter! expected {} at column {} but found {}", expected, column, describe(&token)))
+    }
+
+    fn path(&self, key: &str, column: usize) -> Result<Path, ExpectedError> {
+        Path::parse(key, self.strict).map_err(|err| {
+            ExpectedError::InvalidError(format!("invalid filter! key at column {} is invalid! {}", column, err))
+        })
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ExpectedError> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ExpectedError> {
+        let mut expr = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ExpectedError> {
+        match self.next() {
+            Some((Token::Not, _)) => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Some((Token::Open, _)) => {
+                let expr = self.parse_or()?;
+                self.expect(Token::Close, "')'")?;
+                Ok(expr)
+            }
+            Some((Token::Word(word), _)) if word == "exists" && self.peek() == Some(&Token::Open) => {
+                self.next();
+                let key = self.parse_key()?;
+                self.expect(Token::Close, "')'")?;
+                Ok(Expr::Exists(key))
+            }
+            Some((Token::Word(key), column)) => {
+                let path = self.path(key.as_str(), column)?;
+                self.parse_condition(path)
+            }
+            token => Err(self.error("key, '!' or '('", token)),
+        }
+    }
+
+    fn parse_condition(&mut self, key: Path) -> Result<Expr, ExpectedError> {
+        match self.next() {
+            Some((Token::Op(Operator::Match), _)) => {
+                let column = self.tokens.get(self.pos).map_or(self.end, |(_, column)| { *column });
+                let pattern = self.parse_value()?;
+                match Regex::new(pattern.as_str()) {
+                    Ok(regex) => Ok(Expr::Match(key, regex)),
+                    Err(err) => Err(ExpectedError::InvalidError(format!("invalid filter! regex at column {} is invalid! pattern={}, error={}", column, pattern, err))),
+                }
+            }
+            Some((Token::Op(operator), _)) => Ok(Expr::Compare(key, operator, self.parse_value()?)),
+            Some((Token::Word(word), _)) if word == "in" => {
+                self.expect(Token::Open, "'('")?;
+                let mut items = vec![self.parse_value()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.next();
+                    items.push(self.parse_value()?);
+                }
+                self.expect(Token::Close, "',' or ')'")?;
+                Ok(Expr::In(key, items))
+            }
+            token => Err(self.error(format!("operator after key '{}'", key.as_str()).as_str(), token)),
+        }
+    }
+
+    fn parse_key(&mut self) -> Result<Path, ExpectedError> {
+        match self.next() {
+            Some((Token::Word(key), column)) => self.path(key.as_str(), column),
+            token => Err(self.error("key", token)),
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<String, ExpectedError> {
+        match self.next() {
+            Some((Token::Word(value), _)) | Some((Token::Quoted(value), _)) => Ok(value),
+            token => Err(self.error("value", token)),
+        }
+    }
+
+    fn expect(&mut self, expected: Token, name: &str) -> Result<(), ExpectedError> {
+        match self.next() {
+            Some((token, _)) if token == expected => Ok(()),
+            token => Err(self.error(name, token)),
+        }
+    }
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+fn equals(found: &Value, value: &str) -> bool {
+    let text = to_text(found);
+    if text == value {
+        return true;
+    }
+    match (number(text.as_str()), number(value)) {
+        (Some(left), Some(right)) => left.cmp(&right) == Some(Ordering::Equal),
+        _ => false,
+    }
+}
+
+/// numbers and hex quantities are compared by value, other strings by text.
+fn compare(found: &Value, value: &str) -> Option<Ordering> {
+    if found.is_null() {
+        return None;
+    }
+    let text = to_text(found);
+    match (number(text.as_str()), number(value)) {
+        (Some(left), Some(right)) => left.cmp(&right),
+        (None, None) => Some(text.as_str().cmp(value)),
+        _ => None,
+    }
+}
+
+enum Number {
+    /// hex digits without leading zeros
+    Int(String),
+    Float(f64),
+}
+
+impl Number {
+    fn cmp(&self, other: &Number) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Int(left), Number::Int(right)) => Some(left.len().cmp(&right.len()).then(left.cmp(right))),
+            _ => self.to_f64().partial_cmp(&other.to_f64()),
+        }
+    }
+
+    fn to_f64(&self) -> f64 {
+        match self {
+            Number::Int(digits) => digits.chars().fold(0f64, |acc, c| { acc * 16.0 + c.to_digit(16).unwrap() as f64 }),
+            Number::Float(float) => *float,
+        }
+    }
+}
+
+fn number(text: &str) -> Option<Number> {
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| { text.strip_prefix("0X") }) {
+        if hex.is_empty() || !hex.chars().all(|c| { c.is_ascii_hexdigit() }) {
+            return None;
+        }
+        let trimmed = hex.trim_start_matches('0').to_lowercase();
+        return Some(Number::Int(if trimmed.is_empty() { String::from("0") } else { trimmed }));
+    }
+    if !text.is_empty() && text.chars().all(|c| { c.is_ascii_digit() }) {
+        if let Ok(int) = text.parse::<u128>() {
+            return Some(Number::Int(format!("{:x}", int)));
+        }
+    }
+    text.parse::<f64>().ok().filter(|float| { float.is_finite() }).map(Number::Float)
+}
+
+#[cfg(test)]
+mod filter_test {
+    use serde_json::{json, Map, Value};
+
+    use crate::libs::filter::Filter;
+
+    fn tx() -> Map<String, Value> {
+        json!({
+            "from": "0xa9d1e08c7793af67e9d92fe308d5697fb81d3e43",
+            "to": "0x28c6c06298d514db089934071355e5743bf21d60",
+            "value": "0xde0b6b3a7640000",
+            "gas": 21000,
+            "input": "0xa9059cbb000000000000000000000000",
+            "receipt": {"status": "0x1", "contractAddress": null},
+            "logs": [{"address": "0xdac17f958d2ee523a2206206994597c13d831ec7"}]
+        }).as_object().unwrap().clone()
+    }
+
+    fn matches(filter: &str) -> bool {
+        Filter::parse(filter, false).unwrap().matches(&tx()).unwrap()
+    }
+
+    #[test]
+    fn filter_compare_test() {
+        assert!(matches("value > 0xde0b6b3a763ffff"));
+        assert!(matches("value >= 1000000000000000000"));
+        assert!(!matches("value < 0x0de0b6b3a7640000"));
+        assert!(matches("value = 0x0de0b6b3a7640000"));
+        assert!(matches("gas <= 21000 & gas > 0x5207"));
+        assert!(matches("receipt.status != 0x0"));
+    }
+
+    #[test]
+    fn filter_in_regex_exists_test() {
+        assert!(matches("to in (0x0, '0x28c6c06298d514db089934071355e5743bf21d60')"));
+        assert!(!matches("from in (0x0, 0x1)"));
+        assert!(matches("input ~ '^0xa9059cbb'"));
+        assert!(!matches("exists(contractAddress)"));
+        assert!(matches("exists(receipt.status) & !exists(blobGasUsed)"));
+    }
+
+    #[test]
+    fn filter_array_test() {
+        let values = json!({"transfer": {"recipient": ["cosmos1abc", "cosmos1def"]}}).as_object().unwrap().clone();
+        let filter = Filter::parse("transfer.recipient = cosmos1def", false).unwrap();
+        assert_eq!(filter.evaluate(&values).unwrap(), (true, vec![String::from("transfer.recipient[1]")]));
+        assert!(!Filter::parse("transfer.recipient ~ '^osmo'", false).unwrap().matches(&values).unwrap());
+    }
+
+    #[test]
+    fn filter_precedence_test() {
+        assert!(matches("gas = 1 & gas = 2 | gas = 21000"));
+        assert!(!matches("gas = 1 & (gas = 2 | gas = 21000)"));
+        assert!(matches("!(gas = 1 | gas = 2)"));
+    }
+
+    #[test]
+    fn filter_invalid_test() {
+        assert!(Filter::parse("value >", false).is_err());
+        assert!(Filter::parse("(gas = 1", false).is_err());
+        assert!(Filter::parse("input ~ '('", false).is_err());
+        assert!(Filter::parse("to in 0x0", false).is_err());
+        assert!(Filter::compile("gas = 1 gas", false).matches(&tx()).is_err());
+        assert!(Filter::parse("  ", false).unwrap().matches(&tx()).unwrap());
+    }
+
+    #[test]
+    fn filter_error_column_test() {
+        let err = Filter::parse("(gas = 1 | gas = 2", false).unwrap_err().to_string();
+        assert_eq!(err, "invalid filter! expected ')' at column 19 but found end of filter");
+
+        let err = Filter::parse("gas = 1 & = 2", false).unwrap_err().to_string();
+        assert_eq!(err, "invalid filter! expected key, '!' or '(' at column 11 but found '='");
+
+        let err = Filter::parse("gas = 1 gas", false).unwrap_err().to_string();
+        assert_eq!(err, "invalid filter! expected '&', '|' or end of filter at column 9 but found 'gas'");
+
+        let err = Filter::parse("input ~ 'abc", false).unwrap_err().to_string();
+        assert_eq!(err, "invalid filter! quote at column 9 is not closed!");
+    }
+
+    #[test]
+    fn filter_matched_paths_test() {
+        let filter = Filter::parse("status = 0x1 & (gas = 1 | address ~ '^0xdac1') & !(value = 0x0)", false).unwrap();
+        let (result, paths) = filter.evaluate(&tx()).unwrap();
+        assert!(result);
+        assert_eq!(paths, vec!["receipt.status", "logs[0].address"]);
+
+        let filter = Filter::parse("status = 0x1 & gas = 1", false).unwrap();
+        assert_eq!(filter.evaluate(&tx()).unwrap(), (false, Vec::new()));
+    }
+
+    #[test]
+    fn filter_success_test() {
+        let mut test_map = Map::new();
+        test_map.insert(String::from("key1"), Value::String(String::from("val1")));
+        test_map.insert(String::from("key2"), json!({"sub_key1": "sub_val1"}));
+        test_map.insert(String::from("key3"), json!(100));
+
+        let filter = Filter::parse("(key1 = val1 & sub_key1 = sub_val1 & key3 =101) | key4=null | key3=101", false).unwrap();
+        assert!(filter.matches(&test_map).unwrap());
+    }
+
+    #[test]
+    fn filter_fail_test() {
+        let mut test_map = Map::new();
+        test_map.insert(String::from("key1"), Value::String(String::from("val1")));
+        test_map.insert(String::from("key2"), json!({"sub_key1": "sub_val1"}));
+        test_map.insert(String::from("key3"), json!(100));
+        test_map.insert(String::from("key4"), Value::String(String::from("not_null")));
+
+        let filter = Filter::parse("(key1 = val1 & sub_key1 = sub_val1 & key3 =100) & key4=null", false).unwrap();
+        assert!(!filter.matches(&test_map).unwrap());
+    }
+}
@@ -0,0 +1,68 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use serde_json::Value;
+
+/*
+ * HTTP node for tests. it serves a local port on its own thread, and answers every request by the handler.
+ * examples include this file too, so the helpers building the types of the crate are for tests only.
+ */
+/// request to a mock node. `idx` counts the requests from 0.
+pub struct MockRequest {
+    pub idx: usize,
+    /// path with the query. ex) "/block?height=5"
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+/// starts a mock node answering the status and the JSON body of the handler, and returns its url.
+pub fn mock_node<F>(handler: F) -> String where F: Fn(&MockRequest) -> (u16, String) + Send + 'static {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for (idx, stream) in listener.incoming().enumerate() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+                if line.to_lowercase().starts_with("content-length:") {
+                    content_length = line[15..].trim().parse::<usize>().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let path = String::from(request_line.split_whitespace().nth(1).unwrap_or("/"));
+            let (status, response) = handler(&MockRequest { idx, path, body });
+            let _ = write!(stream, "HTTP/1.1 {} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, response.len(), response);
+        }
+    });
+    format!("http://{}", addr)
+}
+
+/// `mock_node` whose handler answers JSON.
+pub fn mock_json_node<F>(handler: F) -> String where F: Fn(&MockRequest) -> (u16, Value) + Send + 'static {
+    mock_node(move |request| {
+        let (status, response) = handler(request);
+        (status, response.to_string())
+    })
+}
+
+/// subscription of `chain` from `params` over `"target": "block"` and `"start_height": 1`.
+/// ex) sub_event("bitcoin", json!({"sub_id": "mainnet", "nodes": ["http://127.0.0.1:8332"]}))
+#[cfg(test)]
+pub fn sub_event(chain: &str, params: Value) -> crate::types::subscribe::SubscribeEvent {
+    let mut sub_params = serde_json::Map::new();
+    sub_params.insert(String::from("target"), Value::String(String::from("block")));
+    sub_params.insert(String::from("start_height"), Value::from(1u64));
+    sub_params.extend(params.as_object().unwrap().clone());
+    crate::types::subscribe::SubscribeEvent::new(chain, &sub_params)
+}
@@ -2,8 +2,15 @@ use jsonrpc_core::Value;
 use serde_json::Map;
 
 use crate::error::error::ExpectedError;
+use crate::types::enumeration::Enumeration;
+use crate::types::mysql::Format;
 
 pub fn get_params(params: &Map<String, Value>) -> mysql::Params {
+    mysql::Params::from(get_values(params))
+}
+
+/// positional values in the order of the params.
+pub fn get_values(params: &Map<String, Value>) -> Vec<mysql::Value> {
     let mut vec: Vec<mysql::Value> = Vec::new();
     for (_, value) in params.iter() {
         match value {
@@ -22,32 +29,246 @@ pub fn get_params(params: &Map<String, Value>) -> mysql::Params {
             _ => vec.push(mysql::Value::Bytes(value.to_string().as_bytes().to_vec())),
         }
     }
-    mysql::Params::from(vec)
-}
-
-pub fn convert_type(_type: String, max_length: Option<u32>) -> Result<String, ExpectedError> {
-    let converted = if _type == "string" {
-        if max_length.is_some() && max_length.unwrap() <= 65535 {
-            "varchar"
-        } else {
-            "text"
-        }
-    } else if _type == "integer" {
-        if max_length.is_some() && max_length.unwrap() <= 11 {
-            "int"
-        } else {
-            "bigint"
-        }
-    } else if _type == "number" {
-        "double"
-    } else if _type == "boolean" {
-        "boolean"
-    } else if _type == "object" {
-        "json"
-    } else if _type == "array" {
-        "text"
-    } else {
-        return Err(ExpectedError::TypeError(String::from("unsupported type!")));
+    vec
+}
+
+/// single row insert query repeating its values for `rows` rows. a clause after the values is kept.
+/// ex) "insert into t (`a`, `b`) values (?, ?)" -> "insert into t (`a`, `b`) values (?, ?), (?, ?)"
+pub fn multi_row_query(insert_query: &str, rows: usize) -> String {
+    match insert_query.find(" values ") {
+        Some(idx) => {
+            let (columns, values) = insert_query.split_at(idx + " values ".len());
+            let (row, rest) = values.split_at(values.find(')').map_or(values.len(), |end| { end + 1 }));
+            format!("{}{}{}", columns, vec![row; rows].join(", "), rest)
+        }
+        None => String::from(insert_query),
+    }
+}
+
+/// mysql column type of a JSON schema attribute. ex) {"type": "string", "maxLength": 100} -> "varchar(100)"
+/// - `format`: "date-time" -> datetime(6), "uint256"(decimal or 0x hex) -> decimal(78,0)
+/// - integer: the smallest type holding `minimum` and `maximum`, unsigned if `minimum` >= 0. bigint if not given.
+/// - number: decimal of the scale of `multipleOf`(ex. 0.01 -> decimal(65,2)), double if not given.
+pub fn column_type(_type: &str, spec: &Map<String, Value>) -> Result<String, ExpectedError> {
+    let max_length = match spec.get("maxLength") {
+        None => None,
+        Some(max_length) => Some(max_length.as_u64().ok_or(ExpectedError::TypeError(String::from("maxLength must be an unsigned integer!")))?),
+    };
+    if let Some(format) = spec.get("format") {
+        let format = format.as_str().and_then(Format::find).ok_or(ExpectedError::InvalidError(format!("unsupported format! format={}", format)))?;
+        if _type != "string" {
+            return Err(ExpectedError::InvalidError(format!("{} format requires string type!", format.value())));
+        }
+        let converted = match format {
+            Format::DateTime => "datetime(6)",
+            Format::Uint256 => "decimal(78,0)",
+        };
+        return Ok(String::from(converted));
+    }
+    let converted = match _type {
+        "string" => match max_length {
+            Some(max_length) if max_length <= 65535 => format!("varchar({})", max_length),
+            _ => String::from("text"),
+        },
+        "integer" => integer_type(spec, max_length)?,
+        "number" => match spec.get("multipleOf") {
+            None => String::from("double"),
+            Some(multiple_of) => format!("decimal(65,{})", scale(multiple_of)?),
+        },
+        "boolean" => String::from("boolean"),
+        "object" => String::from("json"),
+        "array" => String::from("text"),
+        _ => return Err(ExpectedError::TypeError(format!("unsupported type! type={}", _type))),
+    };
+    Ok(converted)
+}
+
+fn integer_type(spec: &Map<String, Value>, max_length: Option<u64>) -> Result<String, ExpectedError> {
+    let bound = |name: &str| -> Result<Option<i128>, ExpectedError> {
+        match spec.get(name) {
+            None => Ok(None),
+            Some(bound) => match bound.as_i64().map(i128::from).or_else(|| { bound.as_u64().map(i128::from) }) {
+                Some(bound) => Ok(Some(bound)),
+                None => Err(ExpectedError::TypeError(format!("{} must be an integer!", name))),
+            },
+        }
+    };
+    let (minimum, maximum) = (bound("minimum")?, bound("maximum")?);
+    if minimum.is_none() && maximum.is_none() {
+        return Ok(match max_length {
+            Some(max_length) if max_length <= 11 => format!("int({})", max_length),
+            Some(max_length) => format!("bigint({})", max_length),
+            None => String::from("bigint"),
+        });
+    }
+    let unsigned = minimum.map_or(false, |minimum| { minimum >= 0 });
+    let (minimum, maximum) = (minimum.unwrap_or(i64::MIN as i128), maximum.unwrap_or(if unsigned { u64::MAX as i128 } else { i64::MAX as i128 }));
+    if minimum > maximum {
+        return Err(ExpectedError::InvalidError(String::from("minimum cannot be bigger than maximum!")));
+    }
+    for (name, bits) in [("tinyint", 8), ("smallint", 16), ("mediumint", 24), ("int", 32), ("bigint", 64)].iter() {
+        if unsigned && maximum < (1i128 << *bits) {
+            return Ok(format!("{} unsigned", name));
+        }
+        if !unsigned && minimum >= -(1i128 << (*bits - 1)) && maximum < (1i128 << (*bits - 1)) {
+            return Ok(String::from(*name));
+        }
+    }
+    Err(ExpectedError::InvalidError(String::from("integer range does not fit in bigint! use uint256 format instead")))
+}
+
+/// digits after the point of a power of ten. ex) 0.001 -> 3
+fn scale(multiple_of: &Value) -> Result<usize, ExpectedError> {
+    let invalid = || { ExpectedError::InvalidError(String::from("multipleOf must be a power of ten up to 1! ex) 0.01")) };
+    let multiple_of = multiple_of.as_f64().ok_or_else(invalid)?;
+    (0..=30).find(|scale| { (multiple_of * 10f64.powi(*scale as i32) - 1.0).abs() < 1e-9 }).ok_or_else(invalid)
+}
+
+/// "YYYY-MM-DD hh:mm:ss.ffffff" of an RFC 3339 date-time or unix seconds(number or 0x hex), which is left as it is otherwise.
+/// ex) "2021-08-01T09:30:00.123456789Z" -> "2021-08-01 09:30:00.123456", "0x61066f68" -> "2021-08-01 09:54:48"
+pub fn datetime(value: &Value) -> Value {
+    let seconds = match value {
+        Value::Number(number) => number.as_u64(),
+        Value::String(string) if string.starts_with("0x") => u64::from_str_radix(&string[2..], 16).ok(),
+        _ => None,
+    };
+    if let Some(seconds) = seconds {
+        let (days, rest) = ((seconds / 86400) as i64, seconds % 86400);
+        let (year, month, day) = civil_from_days(days);
+        return Value::String(format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, rest / 3600, rest % 3600 / 60, rest % 60));
+    }
+    match value.as_str() {
+        Some(string) if string.len() >= 19 && string.as_bytes()[10] == b'T' => {
+            let utc = string.strip_suffix('Z').or_else(|| { string.strip_suffix("+00:00") });
+            match utc {
+                Some(utc) => {
+                    let (time, fraction) = utc.split_at(19);
+                    let fraction: String = fraction.chars().take(7).collect();
+                    Value::String(format!("{} {}{}", &time[..10], &time[11..], fraction))
+                }
+                // mysql takes the offset of a datetime literal.
+                None => Value::String(string.replacen('T', " ", 1)),
+            }
+        }
+        _ => value.clone(),
+    }
+}
+
+/// ex) 0 -> (1970, 1, 1)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// decimal string of a 0x hex quantity, which is left as it is otherwise. ex) "0xde0b6b3a7640000" -> "1000000000000000000"
+pub fn uint256(value: &Value) -> Value {
+    let hex = match value.as_str().and_then(|string| { string.strip_prefix("0x") }) {
+        Some(hex) if !hex.is_empty() && hex.len() <= 64 && hex.chars().all(|c| { c.is_ascii_hexdigit() }) => hex,
+        _ => return value.clone(),
     };
-    Ok(String::from(converted))
-}
\ No newline at end of file
+    // little endian limbs of 10^9
+    let mut limbs: Vec<u64> = vec![0];
+    for digit in hex.chars().map(|c| { c.to_digit(16).unwrap() as u64 }) {
+        let mut carry = digit;
+        for limb in limbs.iter_mut() {
+            let next = *limb * 16 + carry;
+            *limb = next % 1_000_000_000;
+            carry = next / 1_000_000_000;
+        }
+        if carry > 0 {
+            limbs.push(carry);
+        }
+    }
+    let mut decimal = limbs.last().unwrap().to_string();
+    for limb in limbs.iter().rev().skip(1) {
+        decimal.push_str(format!("{:09}", limb).as_str());
+    }
+    Value::String(decimal)
+}
+
+#[cfg(test)]
+mod mysql_test {
+    use serde_json::{json, Map, Value};
+
+    use crate::libs::mysql::{column_type, datetime, multi_row_query, uint256};
+
+    fn spec(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn multi_row_query_test() {
+        let query = "insert into tm_block (`height`, `hash`) values (?, ?)";
+        assert_eq!(multi_row_query(query, 1), query);
+        assert_eq!(multi_row_query(query, 3), "insert into tm_block (`height`, `hash`) values (?, ?), (?, ?), (?, ?)");
+
+        let upsert = "insert into tm_block (`height`, `hash`) values (?, ?) on duplicate key update `hash`=values(`hash`)";
+        assert_eq!(multi_row_query(upsert, 2), "insert into tm_block (`height`, `hash`) values (?, ?), (?, ?) on duplicate key update `hash`=values(`hash`)");
+    }
+
+    #[test]
+    fn column_type_string_test() {
+        assert_eq!(column_type("string", &spec(json!({"maxLength": 100}))).unwrap(), "varchar(100)");
+        assert_eq!(column_type("string", &spec(json!({"maxLength": 70000}))).unwrap(), "text");
+        assert_eq!(column_type("string", &spec(json!({}))).unwrap(), "text");
+        assert_eq!(column_type("string", &spec(json!({"format": "date-time"}))).unwrap(), "datetime(6)");
+        assert_eq!(column_type("string", &spec(json!({"format": "uint256"}))).unwrap(), "decimal(78,0)");
+        assert!(column_type("string", &spec(json!({"format": "email"}))).is_err());
+        assert!(column_type("integer", &spec(json!({"format": "uint256"}))).is_err());
+        assert!(column_type("string", &spec(json!({"maxLength": "100"}))).is_err());
+    }
+
+    #[test]
+    fn column_type_integer_test() {
+        assert_eq!(column_type("integer", &spec(json!({}))).unwrap(), "bigint");
+        assert_eq!(column_type("integer", &spec(json!({"maxLength": 11}))).unwrap(), "int(11)");
+        assert_eq!(column_type("integer", &spec(json!({"maxLength": 20}))).unwrap(), "bigint(20)");
+        assert_eq!(column_type("integer", &spec(json!({"minimum": 0, "maximum": 255}))).unwrap(), "tinyint unsigned");
+        assert_eq!(column_type("integer", &spec(json!({"minimum": -128, "maximum": 127}))).unwrap(), "tinyint");
+        assert_eq!(column_type("integer", &spec(json!({"minimum": 0, "maximum": 65536}))).unwrap(), "mediumint unsigned");
+        assert_eq!(column_type("integer", &spec(json!({"minimum": -1, "maximum": 4294967295u64}))).unwrap(), "bigint");
+        assert_eq!(column_type("integer", &spec(json!({"minimum": 0}))).unwrap(), "bigint unsigned");
+        assert_eq!(column_type("integer", &spec(json!({"maximum": 100}))).unwrap(), "bigint");
+        assert!(column_type("integer", &spec(json!({"minimum": 10, "maximum": 1}))).is_err());
+        assert!(column_type("integer", &spec(json!({"minimum": 0.5}))).is_err());
+    }
+
+    #[test]
+    fn column_type_other_test() {
+        assert_eq!(column_type("number", &spec(json!({}))).unwrap(), "double");
+        assert_eq!(column_type("number", &spec(json!({"multipleOf": 0.01}))).unwrap(), "decimal(65,2)");
+        assert_eq!(column_type("number", &spec(json!({"multipleOf": 1}))).unwrap(), "decimal(65,0)");
+        assert!(column_type("number", &spec(json!({"multipleOf": 0.5}))).is_err());
+        assert_eq!(column_type("boolean", &spec(json!({}))).unwrap(), "boolean");
+        assert_eq!(column_type("object", &spec(json!({}))).unwrap(), "json");
+        assert_eq!(column_type("array", &spec(json!({}))).unwrap(), "text");
+        assert!(column_type("date", &spec(json!({}))).is_err());
+    }
+
+    #[test]
+    fn datetime_test() {
+        assert_eq!(datetime(&json!("2021-08-01T09:30:00.123456789Z")), json!("2021-08-01 09:30:00.123456"));
+        assert_eq!(datetime(&json!("2021-08-01T09:30:00Z")), json!("2021-08-01 09:30:00"));
+        assert_eq!(datetime(&json!("2021-08-01T18:30:00+09:00")), json!("2021-08-01 18:30:00+09:00"));
+        assert_eq!(datetime(&json!("0x61066f68")), json!("2021-08-01 09:54:48"));
+        assert_eq!(datetime(&json!(0)), json!("1970-01-01 00:00:00"));
+        assert_eq!(datetime(&json!(null)), json!(null));
+    }
+
+    #[test]
+    fn uint256_test() {
+        assert_eq!(uint256(&json!("0xde0b6b3a7640000")), json!("1000000000000000000"));
+        assert_eq!(uint256(&json!("0x0")), json!("0"));
+        assert_eq!(uint256(&json!(format!("0x{}", "f".repeat(64)))), json!("115792089237316195423570985008687907853269984665640564039457584007913129639935"));
+        assert_eq!(uint256(&json!("12345")), json!("12345"));
+        assert_eq!(uint256(&json!(null)), json!(null));
+    }
+}
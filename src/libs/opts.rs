@@ -12,6 +12,17 @@ pub fn string(key: &str) -> Result<String, ExpectedError> {
     Ok(string.to_string())
 }
 
+/// none if the option is not given, and an error naming the option if it is not a number.
+pub fn u64(key: &str) -> Result<Option<u64>, ExpectedError> {
+    match app::value_of(key) {
+        None => Ok(None),
+        Some(value) => match value.parse::<u64>() {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(_) => Err(ExpectedError::ParsingError(format!("{} is not u64! value={}", key, value))),
+        },
+    }
+}
+
 pub fn opt_to_result<T>(option: Option<T>) -> Result<T, ExpectedError> {
     match option {
         Some(t) => Ok(t),
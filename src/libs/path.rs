@@ -0,0 +1,302 @@
+use serde_json::{Map, Value};
+
+use crate::error::error::ExpectedError;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    /// every element of an array or every value of an object
+    Wildcard,
+    /// `key` at any depth
+    Descendant(String),
+}
+
+/// JSONPath subset used to find values for filters and MySQL columns.
+/// ex) `$.tx.body.messages[0].@type`, `tx.body.messages[*].amount`, `$..attributes[*].key`, `['@type']`
+/// `$` is the root and can be omitted. a bare `key` without `.` or `[` is searched at any depth unless strict.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    raw: String,
+    segments: Vec<Segment>,
+    /// bare key searched at any depth, which resolves to the first value found.
+    fuzzy: bool,
+}
+
+impl Path {
+    pub fn parse(path: &str, strict: bool) -> Result<Self, ExpectedError> {
+        let raw = path.trim();
+        if raw.is_empty() {
+            return Err(ExpectedError::InvalidError(String::from("path cannot be empty!")));
+        }
+        if !strict && !raw.starts_with('$') && !raw.contains('.') && !raw.contains('[') {
+            return Ok(Path { raw: String::from(raw), segments: vec![Segment::Descendant(String::from(raw))], fuzzy: true });
+        }
+        // offset converts the index of `chars` to the column of `raw`
+        let (chars, offset): (Vec<char>, usize) = match raw.strip_prefix('$') {
+            Some(rest) => (rest.chars().collect(), 2),
+            None if raw.starts_with('[') => (raw.chars().collect(), 1),
+            None => (format!(".{}", raw).chars().collect(), 0),
+        };
+        let segments = parse_segments(&chars).map_err(|(idx, reason)| {
+            ExpectedError::InvalidError(format!("invalid path! {} at column {}, path={}", reason, idx + offset, raw))
+        })?;
+        Ok(Path { raw: String::from(raw), segments, fuzzy: false })
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.raw.as_str()
+    }
+
+    /// true if the path can select more than one value.
+    pub fn is_multiple(&self) -> bool {
+        !self.fuzzy && self.segments.iter().any(|segment| { matches!(segment, Segment::Wildcard | Segment::Descendant(_)) })
+    }
+
+    /// every value selected by the path with its concrete path. ex) `tx.body.messages[0].amount`
+    pub fn select<'a>(&self, values: &'a Map<String, Value>) -> Vec<(String, &'a Value)> {
+        let mut selected: Vec<(String, &'a Value)> = Vec::new();
+        let mut segments = self.segments.iter();
+        if let Some(first) = segments.next() {
+            select_object(first, "", values, &mut selected);
+        }
+        for segment in segments {
+            let mut next: Vec<(String, &'a Value)> = Vec::new();
+            for (path, value) in selected.iter() {
+                select_value(segment, path, value, &mut next);
+            }
+            selected = next;
+        }
+        selected
+    }
+
+    /// non-null values selected by the path. a fuzzy path resolves to the first one only,
+    /// and its top level key hides nested ones even if null.
+    pub fn resolve<'a>(&self, values: &'a Map<String, Value>) -> Vec<(String, &'a Value)> {
+        let selected = self.select(values);
+        if self.fuzzy && values.contains_key(self.raw.as_str()) {
+            return selected.into_iter().take(1).filter(|(_, value)| { !value.is_null() }).collect();
+        }
+        let resolved = selected.into_iter().filter(|(_, value)| { !value.is_null() });
+        if self.fuzzy {
+            resolved.take(1).collect()
+        } else {
+            resolved.collect()
+        }
+    }
+
+    /// value of a MySQL column. multiple values are kept as an array.
+    pub fn value(&self, values: &Map<String, Value>) -> Value {
+        let resolved = self.resolve(values);
+        if self.is_multiple() {
+            Value::Array(resolved.into_iter().map(|(_, value)| { value.clone() }).collect())
+        } else {
+            resolved.first().map_or(Value::Null, |(_, value)| { (*value).clone() })
+        }
+    }
+}
+
+/// segments of a path whose `$` is stripped. errors have the index of the failed char.
+fn parse_segments(chars: &[char]) -> Result<Vec<Segment>, (usize, String)> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        match chars[idx] {
+            '.' if chars.get(idx + 1) == Some(&'.') => {
+                let (name, next) = read_name(chars, idx + 2);
+                if name.is_empty() {
+                    return Err((idx + 2, String::from("key is missing after '..'")));
+                }
+                segments.push(Segment::Descendant(name));
+                idx = next;
+            }
+            '.' if chars.get(idx + 1) == Some(&'*') => {
+                segments.push(Segment::Wildcard);
+                idx += 2;
+            }
+            '.' => {
+                let (name, next) = read_name(chars, idx + 1);
+                if name.is_empty() {
+                    return Err((idx + 1, String::from("key is missing after '.'")));
+                }
+                segments.push(Segment::Key(name));
+                idx = next;
+            }
+            '[' => {
+                let (segment, next) = read_bracket(chars, idx)?;
+                segments.push(segment);
+                idx = next;
+            }
+            c => return Err((idx, format!("unexpected '{}'", c))),
+        }
+    }
+    Ok(segments)
+}
+
+fn read_name(chars: &[char], start: usize) -> (String, usize) {
+    let mut idx = start;
+    while idx < chars.len() && chars[idx] != '.' && chars[idx] != '[' {
+        idx += 1;
+    }
+    (chars[start..idx].iter().collect(), idx)
+}
+
+/// `[0]`, `[*]`, `['key']` or `["key"]`
+fn read_bracket(chars: &[char], start: usize) -> Result<(Segment, usize), (usize, String)> {
+    let quote = chars.get(start + 1).filter(|c| { **c == '\'' || **c == '"' });
+    let (segment, end) = match quote {
+        Some(quote) => {
+            let close = (start + 2..chars.len()).find(|idx| { chars[*idx] == *quote });
+            match close {
+                Some(close) => (Segment::Key(chars[start + 2..close].iter().collect()), close + 1),
+                None => return Err((start + 1, String::from("quote is not closed"))),
+            }
+        }
+        None => {
+            let close = match (start + 1..chars.len()).find(|idx| { chars[*idx] == ']' }) {
+                Some(close) => close,
+                None => return Err((start, String::from("'[' is not closed"))),
+            };
+            let inner: String = chars[start + 1..close].iter().collect();
+            let segment = match inner.trim() {
+                "*" => Segment::Wildcard,
+                index => match index.parse::<usize>() {
+                    Ok(index) => Segment::Index(index),
+                    Err(_) => return Err((start + 1, format!("'{}' is not an index", inner))),
+                },
+            };
+            (segment, close)
+        }
+    };
+    if chars.get(end) != Some(&']') {
+        return Err((end, String::from("']' is missing")));
+    }
+    Ok((segment, end + 1))
+}
+
+fn join_key(path: &str, key: &str) -> String {
+    if key.is_empty() || key.contains(|c: char| { c == '.' || c == '[' || c == ']' }) {
+        format!("{}['{}']", path, key)
+    } else if path.is_empty() {
+        String::from(key)
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn select_object<'a>(segment: &Segment, path: &str, object: &'a Map<String, Value>, selected: &mut Vec<(String, &'a Value)>) {
+    match segment {
+        Segment::Key(key) => {
+            if let Some(value) = object.get(key) {
+                selected.push((join_key(path, key), value));
+            }
+        }
+        Segment::Index(_) => {}
+        Segment::Wildcard => {
+            for (key, value) in object.iter() {
+                selected.push((join_key(path, key), value));
+            }
+        }
+        Segment::Descendant(key) => {
+            if let Some(value) = object.get(key) {
+                selected.push((join_key(path, key), value));
+            }
+            for (name, value) in object.iter() {
+                select_value(segment, join_key(path, name).as_str(), value, selected);
+            }
+        }
+    }
+}
+
+fn select_value<'a>(segment: &Segment, path: &str, value: &'a Value, selected: &mut Vec<(String, &'a Value)>) {
+    match value {
+        Value::Object(object) => select_object(segment, path, object, selected),
+        Value::Array(array) => match segment {
+            Segment::Index(index) => {
+                if let Some(element) = array.get(*index) {
+                    selected.push((format!("{}[{}]", path, index), element));
+                }
+            }
+            Segment::Wildcard => {
+                for (index, element) in array.iter().enumerate() {
+                    selected.push((format!("{}[{}]", path, index), element));
+                }
+            }
+            Segment::Descendant(_) => {
+                for (index, element) in array.iter().enumerate() {
+                    select_value(segment, format!("{}[{}]", path, index).as_str(), element, selected);
+                }
+            }
+            Segment::Key(_) => {}
+        },
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod path_test {
+    use serde_json::{json, Map, Value};
+
+    use crate::libs::path::Path;
+
+    fn tx() -> Map<String, Value> {
+        json!({
+            "tx": {"body": {"messages": [
+                {"@type": "/cosmos.bank.v1beta1.MsgSend", "amount": [{"denom": "uatom", "amount": "100"}]},
+                {"@type": "/cosmos.staking.v1beta1.MsgDelegate", "amount": {"denom": "uatom", "amount": "200"}}
+            ]}},
+            "logs": [{"events": [{"type": "transfer", "attributes": [{"key": "amount", "value": "100uatom"}]}]}],
+            "amount": null
+        }).as_object().unwrap().clone()
+    }
+
+    fn paths(path: &Path) -> Vec<String> {
+        path.resolve(&tx()).into_iter().map(|(path, _)| { path }).collect()
+    }
+
+    #[test]
+    fn path_index_test() {
+        let path = Path::parse("tx.body.messages[0].@type", false).unwrap();
+        assert_eq!(path.value(&tx()), json!("/cosmos.bank.v1beta1.MsgSend"));
+
+        let path = Path::parse("$.tx.body.messages[1]['@type']", true).unwrap();
+        assert_eq!(path.value(&tx()), json!("/cosmos.staking.v1beta1.MsgDelegate"));
+
+        let path = Path::parse("tx.body.messages[2].@type", false).unwrap();
+        assert_eq!(path.value(&tx()), Value::Null);
+    }
+
+    #[test]
+    fn path_wildcard_test() {
+        let path = Path::parse("tx.body.messages[*].amount", false).unwrap();
+        assert!(path.is_multiple());
+        assert_eq!(paths(&path), vec!["tx.body.messages[0].amount", "tx.body.messages[1].amount"]);
+
+        let path = Path::parse("$..attributes[*].value", false).unwrap();
+        assert_eq!(path.value(&tx()), json!(["100uatom"]));
+        assert_eq!(paths(&path), vec!["logs[0].events[0].attributes[0].value"]);
+    }
+
+    #[test]
+    fn path_fuzzy_test() {
+        let path = Path::parse("denom", false).unwrap();
+        assert!(!path.is_multiple());
+        assert_eq!(paths(&path), vec!["tx.body.messages[0].amount[0].denom"]);
+
+        let path = Path::parse("denom", true).unwrap();
+        assert_eq!(path.value(&tx()), Value::Null);
+
+        let path = Path::parse("amount", false).unwrap();
+        assert_eq!(path.value(&tx()), Value::Null);
+    }
+
+    #[test]
+    fn path_invalid_test() {
+        assert!(Path::parse("", false).is_err());
+        assert!(Path::parse("messages[", false).is_err());
+        assert!(Path::parse("messages[a]", false).is_err());
+        assert!(Path::parse("messages..", false).is_err());
+        assert!(Path::parse("$.messages['@type]", false).is_err());
+        assert_eq!(Path::parse("tx.body[x]", false).unwrap_err().to_string(), "invalid path! 'x' is not an index at column 9, path=tx.body[x]");
+    }
+}
@@ -0,0 +1,209 @@
+use serde_json::{json, Map, Value};
+
+use crate::error::error::ExpectedError;
+use crate::libs::serde::{get_array, get_str, get_string};
+
+/// Transfer(address,address,uint256) of ERC-20 and ERC-721
+pub const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+/// TransferSingle(address,address,address,uint256,uint256) of ERC-1155
+pub const TRANSFER_SINGLE_TOPIC: &str = "0xc3d58168c5ae7397731d063d5bbf3d657854427343f4c083240f7aacaa2d0f62";
+/// TransferBatch(address,address,address,uint256[],uint256[]) of ERC-1155
+pub const TRANSFER_BATCH_TOPIC: &str = "0x4a39dc06d4c0dbc64b70af90fd698a233a518aa5d07e595d983b8c0526c8f7fb";
+
+/// decodes a transfer log into normalized records. ERC-1155 TransferBatch makes a record per token id.
+pub fn decode_transfer(log: &Map<String, Value>) -> Result<Vec<Value>, ExpectedError> {
+    let topics: Vec<&str> = get_array(log, "topics")?.iter().filter_map(|topic| { topic.as_str() }).collect();
+    let words = data_words(get_str(log, "data")?)?;
+    let topic0 = match topics.first() {
+        Some(topic0) => topic0.to_lowercase(),
+        None => return Err(ExpectedError::InvalidError(String::from("log does not have topics!"))),
+    };
+
+    let transfers = if topic0 == TRANSFER_TOPIC && topics.len() == 3 && words.len() == 1 {
+        vec![("erc20", None, topics[1], topics[2], None, quantity(words[0]), None)]
+    } else if topic0 == TRANSFER_TOPIC && topics.len() == 4 {
+        vec![("erc721", None, topics[1], topics[2], Some(quantity(topics[3])), String::from("0x1"), None)]
+    } else if topic0 == TRANSFER_SINGLE_TOPIC && topics.len() == 4 && words.len() == 2 {
+        vec![("erc1155", Some(topics[1]), topics[2], topics[3], Some(quantity(words[0])), quantity(words[1]), None)]
+    } else if topic0 == TRANSFER_BATCH_TOPIC && topics.len() == 4 {
+        let ids = dynamic_array(&words, 0)?;
+        let amounts = dynamic_array(&words, 1)?;
+        if ids.len() != amounts.len() {
+            return Err(ExpectedError::InvalidError(String::from("ids and values of TransferBatch have different length!")));
+        }
+        ids.iter().zip(amounts.iter()).enumerate()
+            .map(|(idx, (id, amount))| { ("erc1155", Some(topics[1]), topics[2], topics[3], Some(quantity(id)), quantity(amount), Some(idx)) })
+            .collect()
+    } else {
+        return Err(ExpectedError::InvalidError(format!("log is not token transfer! topic0={}", topic0)));
+    };
+
+    let mut records: Vec<Value> = Vec::new();
+    for (standard, operator, from, to, token_id, amount, batch_index) in transfers.into_iter() {
+        records.push(json!({
+            "token": get_string(log, "address")?.to_lowercase(),
+            "standard": standard,
+            "operator": operator.map(address),
+            "from": address(from),
+            "to": address(to),
+            "token_id": token_id,
+            "amount": amount,
+            "batch_index": batch_index,
+            "tx_hash": get_string(log, "transactionHash")?,
+            "log_index": get_string(log, "logIndex")?,
+            "block_number": get_string(log, "blockNumber")?,
+            "block_hash": get_string(log, "blockHash")?,
+        }));
+    }
+    Ok(records)
+}
+
+/// 32 bytes topic to 20 bytes address
+fn address(topic: &str) -> String {
+    let hex = topic.trim_start_matches("0x");
+    let start = if hex.len() > 40 { hex.len() - 40 } else { 0 };
+    format!("0x{}", hex[start..].to_lowercase())
+}
+
+/// uint256 word to quantity without leading zeros. ex) 0x00..0a => 0xa
+fn quantity(word: &str) -> String {
+    let trimmed = word.trim_start_matches("0x").trim_start_matches('0');
+    if trimmed.is_empty() {
+        String::from("0x0")
+    } else {
+        format!("0x{}", trimmed.to_lowercase())
+    }
+}
+
+fn data_words(data: &str) -> Result<Vec<&str>, ExpectedError> {
+    let hex = data.trim_start_matches("0x");
+    if hex.len() % 64 != 0 || !hex.is_ascii() {
+        return Err(ExpectedError::InvalidError(String::from("log data is not 32 bytes words!")));
+    }
+    Ok((0..hex.len() / 64).map(|idx| { &hex[idx * 64..(idx + 1) * 64] }).collect())
+}
+
+/// abi encoded `uint256[]` whose offset is at `words[head]`. the length comes from the log, so it may be anything.
+fn dynamic_array<'a>(words: &[&'a str], head: usize) -> Result<Vec<&'a str>, ExpectedError> {
+    let offset = word_to_usize(words.get(head))? / 32;
+    let length = word_to_usize(words.get(offset))?;
+    let end = match (offset + 1).checked_add(length) {
+        Some(end) => end,
+        None => return Err(ExpectedError::InvalidError(String::from("abi encoded array is out of data!"))),
+    };
+    match words.get(offset + 1..end) {
+        Some(items) => Ok(items.to_vec()),
+        None => Err(ExpectedError::InvalidError(String::from("abi encoded array is out of data!"))),
+    }
+}
+
+fn word_to_usize(word: Option<&&str>) -> Result<usize, ExpectedError> {
+    let word = match word {
+        Some(word) => word.trim_start_matches('0'),
+        None => return Err(ExpectedError::InvalidError(String::from("abi encoded array is out of data!"))),
+    };
+    if word.is_empty() {
+        return Ok(0);
+    }
+    Ok(usize::from_str_radix(word, 16)?)
+}
+
+#[cfg(test)]
+mod token_test {
+    use serde_json::{json, Map, Value};
+
+    use crate::libs::token::{decode_transfer, TRANSFER_BATCH_TOPIC, TRANSFER_SINGLE_TOPIC, TRANSFER_TOPIC};
+
+    const FROM: &str = "0x000000000000000000000000a9d1e08c7793af67e9d92fe308d5697fb81d3e43";
+    const TO: &str = "0x00000000000000000000000028c6c06298d514db089934071355e5743bf21d60";
+
+    fn log(topics: Value, data: &str) -> Map<String, Value> {
+        json!({
+            "address": "0xdAC17F958D2ee523a2206206994597C13D831ec7",
+            "topics": topics,
+            "data": data,
+            "transactionHash": "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060",
+            "logIndex": "0x3",
+            "blockNumber": "0xc5043f",
+            "blockHash": "0x4e3a3754410177e6937ef1f84bba68ea139e8d1a2258c5f85db9f1cd715a1bdd"
+        }).as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn decode_erc20_test() {
+        let log = log(json!([TRANSFER_TOPIC, FROM, TO]), "0x00000000000000000000000000000000000000000000000000000000009896800");
+        assert!(decode_transfer(&log).is_err());
+
+        let log = self::log(json!([TRANSFER_TOPIC, FROM, TO]), "0x0000000000000000000000000000000000000000000000000000000000989680");
+        let records = decode_transfer(&log).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["standard"], "erc20");
+        assert_eq!(records[0]["token"], "0xdac17f958d2ee523a2206206994597c13d831ec7");
+        assert_eq!(records[0]["from"], "0xa9d1e08c7793af67e9d92fe308d5697fb81d3e43");
+        assert_eq!(records[0]["to"], "0x28c6c06298d514db089934071355e5743bf21d60");
+        assert_eq!(records[0]["amount"], "0x989680");
+        assert_eq!(records[0]["token_id"], Value::Null);
+    }
+
+    #[test]
+    fn decode_erc721_test() {
+        let token_id = "0x0000000000000000000000000000000000000000000000000000000000001f40";
+        let log = log(json!([TRANSFER_TOPIC, FROM, TO, token_id]), "0x");
+        let records = decode_transfer(&log).unwrap();
+        assert_eq!(records[0]["standard"], "erc721");
+        assert_eq!(records[0]["token_id"], "0x1f40");
+        assert_eq!(records[0]["amount"], "0x1");
+    }
+
+    #[test]
+    fn decode_erc1155_single_test() {
+        let data = "0x00000000000000000000000000000000000000000000000000000000000000070000000000000000000000000000000000000000000000000000000000000002";
+        let log = log(json!([TRANSFER_SINGLE_TOPIC, FROM, FROM, TO]), data);
+        let records = decode_transfer(&log).unwrap();
+        assert_eq!(records[0]["standard"], "erc1155");
+        assert_eq!(records[0]["operator"], "0xa9d1e08c7793af67e9d92fe308d5697fb81d3e43");
+        assert_eq!(records[0]["token_id"], "0x7");
+        assert_eq!(records[0]["amount"], "0x2");
+    }
+
+    #[test]
+    fn decode_erc1155_batch_test() {
+        let data = [
+            "0x",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "00000000000000000000000000000000000000000000000000000000000000a0",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "000000000000000000000000000000000000000000000000000000000000000a",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        ].concat();
+        let log = log(json!([TRANSFER_BATCH_TOPIC, FROM, FROM, TO]), data.as_str());
+        let records = decode_transfer(&log).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["token_id"], "0x1");
+        assert_eq!(records[0]["amount"], "0xa");
+        assert_eq!(records[1]["token_id"], "0x2");
+        assert_eq!(records[1]["amount"], "0x0");
+        assert_eq!(records[1]["batch_index"], 1);
+    }
+
+    #[test]
+    fn decode_erc1155_batch_overflow_test() {
+        let data = [
+            "0x",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "000000000000000000000000000000000000000000000000ffffffffffffffff",
+        ].concat();
+        let log = log(json!([TRANSFER_BATCH_TOPIC, FROM, FROM, TO]), data.as_str());
+        assert!(decode_transfer(&log).is_err());
+    }
+
+    #[test]
+    fn decode_not_transfer_test() {
+        let log = log(json!(["0x8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925", FROM, TO]), "0x");
+        assert!(decode_transfer(&log).is_err());
+    }
+}
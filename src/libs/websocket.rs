@@ -0,0 +1,36 @@
+use futures::{SinkExt, StreamExt};
+use serde_json::Value;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::error::error::ExpectedError;
+use crate::libs::node;
+
+/// sends `request` on a new connection to `url`, then calls `on_message` with every JSON text message until the connection is closed.
+/// the handshake carries the auth headers of the registered node serving `url`.
+pub async fn subscribe<F>(url: &str, request: &Value, mut on_message: F) -> Result<(), ExpectedError>
+    where F: FnMut(&Value) -> Result<(), ExpectedError> {
+    let mut handshake = url.into_client_request()?;
+    for (name, value) in node::acquire(url).await.iter() {
+        let header = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value.as_str()));
+        if let (Ok(name), Ok(value)) = header {
+            handshake.headers_mut().insert(name, value);
+        }
+    }
+    let (mut stream, _) = connect_async(handshake).await?;
+    stream.send(Message::Text(request.to_string())).await?;
+    while let Some(message) = stream.next().await {
+        match message? {
+            Message::Text(text) => {
+                if let Ok(value) = serde_json::from_str::<Value>(text.as_str()) {
+                    on_message(&value)?;
+                }
+            }
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    Ok(())
+}
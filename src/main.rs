@@ -2,6 +2,7 @@ use appbase::app;
 
 use crate::plugin::tendermint::TendermintPlugin;
 use crate::plugin::ethereum::EthereumPlugin;
+use crate::plugin::bitcoin::BitcoinPlugin;
 
 mod plugin;
 mod types;
@@ -14,6 +15,7 @@ fn main() {
     dotenv::dotenv().ok();
     app::register_plugin::<EthereumPlugin>();
     app::register_plugin::<TendermintPlugin>();
+    app::register_plugin::<BitcoinPlugin>();
     app::register_plugin::<plugin::email::EmailPlugin>();
     app::register_plugin::<plugin::mongo::MongoPlugin>();
     app::register_plugin::<plugin::mysql::MySqlPlugin>();
@@ -22,6 +24,7 @@ fn main() {
     app::register_plugin::<plugin::telegram::TelegramPlugin>();
     app::initialize!(TendermintPlugin);
     app::initialize!(EthereumPlugin);
+    app::initialize!(BitcoinPlugin);
     app::startup();
     app::execute();
 }
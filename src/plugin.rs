@@ -8,3 +8,6 @@ pub mod email;
 pub mod slack;
 pub mod telegram;
 pub mod ethereum;
+pub mod chain;
+pub mod bitcoin;
+pub mod node;
@@ -0,0 +1,212 @@
+use appbase::*;
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+
+use crate::error::error::ExpectedError;
+use crate::libs::opts::opt_to_result;
+use crate::libs::request;
+use crate::libs::serde::{get_array, get_str};
+use crate::plugin::chain::{ChainAdapter, ChainPlugin};
+use crate::plugin::jsonrpc::JsonRpcPlugin;
+use crate::plugin::node::NodePlugin;
+use crate::plugin::rocks::RocksPlugin;
+use crate::types::subscribe::{SubscribeEvent, SubscribeTarget};
+
+pub struct BitcoinPlugin {
+    chain: ChainPlugin<Bitcoin>,
+}
+
+pub struct Bitcoin;
+
+plugin::requires!(BitcoinPlugin; JsonRpcPlugin, RocksPlugin, NodePlugin);
+
+impl Plugin for BitcoinPlugin {
+    fn new() -> Self {
+        app::arg(clap::Arg::new("bitcoin::block-mysql-sync").long("btc-block-mysql-sync"));
+        app::arg(clap::Arg::new("bitcoin::tx-mysql-sync").long("btc-tx-mysql-sync"));
+        app::arg(clap::Arg::new("bitcoin::block-mongo-sync").long("btc-block-mongo-sync"));
+        app::arg(clap::Arg::new("bitcoin::tx-mongo-sync").long("btc-tx-mongo-sync"));
+        app::arg(clap::Arg::new("bitcoin::block-rabbit-mq-publish").long("btc-block-rabbit-mq-publish"));
+        app::arg(clap::Arg::new("bitcoin::tx-rabbit-mq-publish").long("btc-tx-rabbit-mq-publish"));
+
+        BitcoinPlugin {
+            chain: ChainPlugin::new(),
+        }
+    }
+
+    fn initialize(&mut self) {
+        self.chain.initialize();
+    }
+
+    fn startup(&mut self) {
+        self.chain.startup();
+    }
+
+    fn shutdown(&mut self) {
+        self.chain.shutdown();
+    }
+}
+
+#[async_trait]
+impl ChainAdapter for Bitcoin {
+    const CHAIN: &'static str = "bitcoin";
+    const PREFIX: &'static str = "btc";
+    const SCHEMA_FILE: &'static str = "schema/btc_mysql.json";
+    const BLOCK_HEIGHT_COLUMN: &'static str = "height";
+    const TX_HEIGHT_COLUMN: &'static str = "height";
+    const TX_HASH_COLUMN: &'static str = "txid";
+    const TARGETS: &'static [SubscribeTarget] = &[SubscribeTarget::Block, SubscribeTarget::Tx];
+
+    async fn latest_height(_: &SubscribeEvent, node: &str) -> Result<u64, ExpectedError> {
+        let result = Self::call(node, "getblockcount", json!([])).await?;
+        let height = opt_to_result(result.as_u64())?;
+        Ok(height)
+    }
+
+    async fn fetch_block(_: &SubscribeEvent, node: &str, height: u64) -> Result<Value, ExpectedError> {
+        let block_hash = match Self::call(node, "getblockhash", json!([ height ])).await {
+            Ok(block_hash) => block_hash,
+            Err(err) => {
+                return if err.to_string().starts_with("Block height out of range") {
+                    Err(ExpectedError::BlockHeightError(String::from("block has not yet been created!")))
+                } else {
+                    Err(err)
+                };
+            }
+        };
+        let block = Self::call(node, "getblock", json!([ block_hash, 2 ])).await?;
+        if !block.is_object() {
+            return Err(ExpectedError::TypeError(String::from("block is not object!")));
+        }
+        Ok(block)
+    }
+
+    /// txs of verbosity 2 block do not include the block they belong to, so `blockhash`, `height` and `time` are added.
+    async fn fetch_txs(_: &SubscribeEvent, _: &str, height: u64, block: &Value) -> Result<Vec<Value>, ExpectedError> {
+        let block_object = opt_to_result(block.as_object())?;
+        let block_hash = get_str(block_object, "hash")?;
+        let block_time = opt_to_result(block_object.get("time"))?;
+
+        let mut txs: Vec<Value> = Vec::new();
+        for tx in get_array(block_object, "tx")?.iter() {
+            let mut tx_object = opt_to_result(tx.as_object())?.clone();
+            tx_object.insert(String::from("blockhash"), Value::String(String::from(block_hash)));
+            tx_object.insert(String::from("height"), json!(height));
+            tx_object.insert(String::from("time"), block_time.clone());
+            txs.push(Value::Object(tx_object));
+        }
+        Ok(txs)
+    }
+
+    /// full txs are replaced with txids.
+    fn block_record(block: &Value) -> Result<Value, ExpectedError> {
+        let mut block_object = opt_to_result(block.as_object())?.clone();
+        let txids: Vec<Value> = get_array(&block_object, "tx")?.iter()
+            .filter_map(|tx| { tx.get("txid").cloned() })
+            .collect();
+        block_object.insert(String::from("tx"), Value::Array(txids));
+        Ok(Value::Object(block_object))
+    }
+}
+
+impl Bitcoin {
+    async fn call(node: &str, method: &str, params: Value) -> Result<Value, ExpectedError> {
+        let req_body = json!({
+            "jsonrpc": "1.0",
+            "method": method,
+            "params": params,
+            "id": "ufc"
+        });
+        let body: Map<String, Value> = request::post(node, req_body.to_string().as_str()).await?;
+        if let Some(error) = body.get("error") {
+            if !error.is_null() {
+                let message = match error.as_object() {
+                    Some(error_object) => get_str(error_object, "message").unwrap_or("unknown error!").to_string(),
+                    None => error.to_string(),
+                };
+                return Err(ExpectedError::RequestError(message));
+            }
+        }
+        let result = opt_to_result(body.get("result"))?;
+        Ok(result.clone())
+    }
+}
+
+#[cfg(test)]
+mod bitcoin_test {
+    use serde_json::{json, Value};
+
+    use crate::error::error::ExpectedError;
+    use crate::libs::mock;
+    use crate::plugin::bitcoin::Bitcoin;
+    use crate::plugin::chain::ChainAdapter;
+    use crate::types::subscribe::SubscribeEvent;
+
+    const BLOCK_HASH: &str = "00000000839a8e6886ab5951d76f411475428afc90947ee320161bbf18eb6048";
+
+    fn handle(request: &Value) -> (u16, Value) {
+        let params = request.get("params").unwrap();
+        let result = match request.get("method").unwrap().as_str().unwrap() {
+            "getblockcount" => json!(1),
+            "getblockhash" if params[0] == json!(1) => json!(BLOCK_HASH),
+            "getblockhash" => {
+                return (500, json!({"result": null, "error": {"code": -8, "message": "Block height out of range"}, "id": "ufc"}));
+            }
+            "getblock" => json!({
+                "hash": BLOCK_HASH,
+                "height": 1,
+                "time": 1231469665,
+                "previousblockhash": "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+                "tx": [{
+                    "txid": "0e3e2357e806b6cdb1f70b54c3a3a17b6714ee1f0e68bebb44a74b1efd512098",
+                    "vin": [{"coinbase": "04ffff001d0104"}],
+                    "vout": [{"value": 50.0, "n": 0}]
+                }]
+            }),
+            _ => return (500, json!({"result": null, "error": {"code": -32601, "message": "Method not found"}, "id": "ufc"})),
+        };
+        (200, json!({"result": result, "error": null, "id": "ufc"}))
+    }
+
+    fn mock_node() -> String {
+        mock::mock_json_node(|request| { handle(&serde_json::from_slice(&request.body).unwrap()) })
+    }
+
+    fn sub_event(target: &str) -> SubscribeEvent {
+        mock::sub_event("bitcoin", json!({"sub_id": "mainnet", "target": target, "nodes": ["http://127.0.0.1:8332"]}))
+    }
+
+    #[tokio::test]
+    async fn latest_height_test() {
+        let node = mock_node();
+        let height = Bitcoin::latest_height(&sub_event("block"), node.as_str()).await.unwrap();
+        assert_eq!(height, 1);
+    }
+
+    #[tokio::test]
+    async fn fetch_block_test() {
+        let node = mock_node();
+        let block = Bitcoin::fetch_block(&sub_event("block"), node.as_str(), 1).await.unwrap();
+        let record = Bitcoin::block_record(&block).unwrap();
+        assert_eq!(record.get("hash").unwrap(), BLOCK_HASH);
+        assert_eq!(record.get("tx").unwrap(), &json!(["0e3e2357e806b6cdb1f70b54c3a3a17b6714ee1f0e68bebb44a74b1efd512098"]));
+    }
+
+    #[tokio::test]
+    async fn fetch_block_not_created_test() {
+        let node = mock_node();
+        let result = Bitcoin::fetch_block(&sub_event("block"), node.as_str(), 2).await;
+        assert!(matches!(result, Err(ExpectedError::BlockHeightError(_))));
+    }
+
+    #[tokio::test]
+    async fn fetch_txs_test() {
+        let node = mock_node();
+        let sub_event = sub_event("tx");
+        let block = Bitcoin::fetch_block(&sub_event, node.as_str(), 1).await.unwrap();
+        let txs = Bitcoin::fetch_txs(&sub_event, node.as_str(), 1, &block).await.unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].get("blockhash").unwrap(), BLOCK_HASH);
+        assert_eq!(txs[0].get("height").unwrap(), &json!(1));
+    }
+}
@@ -0,0 +1,1049 @@
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use appbase::*;
+use appbase::plugin::State;
+use async_trait::async_trait;
+use futures::future::join_all;
+use jsonrpc_core::Params;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tokio::sync::{mpsc, watch};
+use tokio::time;
+
+use crate::{enumeration, libs, message};
+use crate::error::error::ExpectedError;
+use crate::libs::delivery::{self, MAX_PENDING_HEIGHTS};
+use crate::libs::filter::Filter;
+use crate::libs::mysql::get_params;
+use crate::libs::opts::{opt_ref_to_result, opt_to_result};
+use crate::libs::rocks::{get_by_prefix_static, get_static};
+use crate::libs::serde::{get_bool, get_object, get_str, get_string, select_value};
+use crate::plugin::jsonrpc::JsonRpcPlugin;
+use crate::plugin::mysql::MySqlPlugin;
+use crate::plugin::rocks::{RocksMethod, RocksMsg, RocksPlugin};
+use crate::types::channel::{MultiChannel, next_message};
+use crate::types::enumeration::Enumeration;
+use crate::types::mysql::Schema;
+use crate::types::subscribe::{Fetched, SubscribeEvent, SubscribeStatus, SubscribeTarget, SubscribeTask, timestamp, timestamp_millis};
+use crate::validation::{get_blocks, get_task, get_txs, resubscribe, stop_subscribe, subscribe, unsubscribe, validate_filter};
+
+/*
+ * `ChainAdapter` is the only part that talks to a blockchain node.
+ * `ChainPlugin` drives any adapter: it registers the `{PREFIX}_*` JSON-RPC methods, loads tasks from RocksDB,
+ * polls every subscription and sends the results to MySQL, MongoDB and RabbitMQ.
+ */
+#[async_trait]
+pub trait ChainAdapter: Send + Sync + 'static {
+    /// chain name used for task ids, channel and option names. ex) "ethereum"
+    const CHAIN: &'static str;
+    /// prefix of JSON-RPC methods and MySQL tables. ex) "eth"
+    const PREFIX: &'static str;
+    const SCHEMA_FILE: &'static str;
+    const BLOCK_HEIGHT_COLUMN: &'static str;
+    const TX_HEIGHT_COLUMN: &'static str;
+    const TX_HASH_COLUMN: &'static str;
+    const TARGETS: &'static [SubscribeTarget];
+
+    async fn latest_height(sub_event: &SubscribeEvent, node: &str) -> Result<u64, ExpectedError>;
+
+    /// height the confirmations are counted from. `None` emits blocks as soon as they are created.
+    async fn head_height(sub_event: &SubscribeEvent, node: &str) -> Result<Option<u64>, ExpectedError> {
+        if sub_event.confirmations == 0 {
+            return Ok(None);
+        }
+        Ok(Some(Self::latest_height(sub_event, node).await?))
+    }
+
+    /// returns `ExpectedError::BlockHeightError` if the block has not yet been created.
+    async fn fetch_block(sub_event: &SubscribeEvent, node: &str, height: u64) -> Result<Value, ExpectedError>;
+
+    async fn fetch_txs(sub_event: &SubscribeEvent, node: &str, height: u64, block: &Value) -> Result<Vec<Value>, ExpectedError>;
+
+    /// values of chain specific targets(ex. ethereum log).
+    async fn fetch_target(sub_event: &SubscribeEvent, _node: &str, _height: u64, _block: &Value) -> Result<Vec<Value>, ExpectedError> {
+        Err(ExpectedError::InvalidError(format!("{} target is not supported!", sub_event.target.value())))
+    }
+
+    /// verifies chain specific subscribe params and returns the options kept on the subscription.
+    fn options(_params: &Map<String, Value>) -> Result<Map<String, Value>, ExpectedError> {
+        Ok(Map::new())
+    }
+
+    /// value saved and published for the block target.
+    fn block_record(block: &Value) -> Result<Value, ExpectedError> {
+        Ok(block.clone())
+    }
+
+    /// (hash, parent hash) of the block. chain reorganizations are not detected if `None`.
+    fn block_hashes(_block: &Value) -> Option<(String, String)> {
+        None
+    }
+
+    /// column of `{PREFIX}_{target}` holding the block hash, used to delete values of orphaned blocks.
+    fn block_hash_column(_target: &SubscribeTarget) -> Option<&'static str> {
+        None
+    }
+
+    /// JSON-RPC request subscribing new heads over websocket. `websocket` param is not supported if `None`.
+    fn head_subscription() -> Option<Value> {
+        None
+    }
+
+    /// height of a new head notification. `None` for other messages(ex. result of the subscription request).
+    fn head_notification(_message: &Value) -> Option<u64> {
+        None
+    }
+
+    /// MySQL table and MongoDB collection of the target.
+    fn table(target: &SubscribeTarget) -> String {
+        format!("{}_{}", Self::PREFIX, target.value())
+    }
+
+    /// value the filter is matched on instead of the sent one. `None` matches the sent value.
+    fn filter_view(_value: &Value) -> Option<Value> {
+        None
+    }
+
+    /// height parameter of `{PREFIX}_mysql_get_blocks` and `{PREFIX}_mysql_get_txs`.
+    fn height_param(height: u64) -> Value {
+        json!(height)
+    }
+}
+
+pub struct ChainPlugin<A: ChainAdapter> {
+    sub_events: Option<Vec<SubscribeEvent>>,
+    channels: Option<MultiChannel>,
+    monitor: Option<channel::Receiver>,
+    schema: Option<HashMap<String, Schema>>,
+    adapter: PhantomData<A>,
+}
+
+/// channels and schema every subscription loop sends its values with.
+#[derive(Clone)]
+struct Sinks {
+    rocks: channel::Sender,
+    mysql: channel::Sender,
+    mongo: channel::Sender,
+    rabbit: channel::Sender,
+    schema: Arc<HashMap<String, Schema>>,
+}
+
+/// control senders of running subscription loops by task id.
+type Controls = HashMap<String, mpsc::UnboundedSender<ChainMethod>>;
+
+message!((ChainMsg; {value: Value}); (ChainMethod; {Subscribe: "subscribe"}, {Resubscribe: "resubscribe"}, {Stop: "stop"}, {Unsubscribe: "unsubscribe"}));
+
+impl<A: ChainAdapter> ChainPlugin<A> {
+    pub fn new() -> Self {
+        ChainPlugin {
+            sub_events: None,
+            channels: None,
+            monitor: None,
+            schema: None,
+            adapter: PhantomData,
+        }
+    }
+
+    pub fn initialize(&mut self) {
+        self.init();
+        self.register_jsonrpc();
+        self.load_schema();
+        self.init_mysql();
+        self.load_tasks();
+    }
+
+    /// every subscription runs on its own task. JSON-RPC requests reach them through the chain channel.
+    pub fn startup(&mut self) {
+        let mut monitor = self.monitor.take().unwrap();
+        let sub_events = self.sub_events.take().unwrap();
+        let channels = self.channels.as_ref().unwrap();
+        let sinks = Sinks {
+            rocks: channels.get("rocks"),
+            mysql: channels.get("mysql"),
+            mongo: channels.get("mongo"),
+            rabbit: channels.get("rabbit"),
+            schema: Arc::new(self.schema.as_ref().unwrap().clone()),
+        };
+        let app = app::quit_handle().unwrap();
+
+        app::spawn(async move {
+            let mut controls: Controls = HashMap::new();
+            for sub_event in sub_events.into_iter() {
+                Self::run(sub_event, &sinks, &mut controls);
+            }
+            while let Some(msg) = next_message(&mut monitor).await {
+                Self::message_handler(&msg, &mut controls, &sinks);
+                if app.is_quiting() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// polls while the subscription is workable, sleeping between polls, and otherwise waits for a control message.
+    fn run(mut sub_event: SubscribeEvent, sinks: &Sinks, controls: &mut Controls) {
+        let (control_sender, mut control) = mpsc::unbounded_channel();
+        controls.insert(sub_event.task_id.clone(), control_sender);
+        let sinks = sinks.clone();
+
+        app::spawn(async move {
+            let (head_sender, mut heads) = watch::channel(None);
+            let watcher = sub_event.websocket.as_ref().map(|websocket| { tokio::spawn(Self::watch_heads(websocket.clone(), sub_event.max_backoff(), head_sender)) });
+            loop {
+                let method = if sub_event.is_pollable() {
+                    let mut delay = Self::step(&mut sub_event, &sinks).await;
+                    // new heads wake the loop while the websocket is connected, so polls at the head are only for gaps.
+                    if sub_event.idle_polls > 0 && heads.borrow().is_some() {
+                        delay = delay.max(sub_event.max_backoff());
+                    }
+                    tokio::select! {
+                        method = control.recv() => method,
+                        _ = time::sleep(delay) => continue,
+                        Ok(_) = heads.changed(), if watcher.is_some() => continue,
+                    }
+                } else {
+                    control.recv().await
+                };
+                match method {
+                    Some(method) => {
+                        if !Self::control_handler(method, &mut sub_event, &sinks) {
+                            break;
+                        }
+                    }
+                    None => break,
+                }
+            }
+            if let Some(watcher) = watcher {
+                watcher.abort();
+            }
+        });
+    }
+
+    /// keeps the websocket subscription of new heads, sending the height of every head, and `None` while disconnected.
+    /// it reconnects after `reconnect_delay` until the subscription loop aborts it. a registered name is resolved on every connection.
+    async fn watch_heads(websocket: String, reconnect_delay: Duration, heads: watch::Sender<Option<u64>>) {
+        let request = match A::head_subscription() {
+            Some(request) => request,
+            None => return,
+        };
+        loop {
+            let url = libs::node::resolve(websocket.as_str());
+            let result = libs::websocket::subscribe(url.as_str(), &request, |message| {
+                if let Some(error) = message.get("error").filter(|error| { !error.is_null() }) {
+                    return Err(ExpectedError::RequestError(format!("websocket subscription failed! error={}", error)));
+                }
+                if let Some(height) = A::head_notification(message) {
+                    let _ = heads.send(Some(height));
+                }
+                Ok(())
+            }).await;
+            let _ = heads.send(None);
+            match result {
+                Ok(_) => println!("websocket is closed! websocket={}", libs::node::redact_url(websocket.as_str())),
+                Err(err) => println!("websocket is disconnected! websocket={}, error={}", libs::node::redact_url(websocket.as_str()), err.to_string()),
+            }
+            time::sleep(reconnect_delay).await;
+        }
+    }
+
+    /// polls once with the selected node and returns the delay before the next poll.
+    /// an error subscription returns to working once its node answers.
+    async fn step(sub_event: &mut SubscribeEvent, sinks: &Sinks) -> Duration {
+        if delivery::take_progress(sub_event.task_id.as_str()) {
+            Self::sync_event(&sinks.rocks, sub_event);
+        }
+        if delivery::pending(sub_event.task_id.as_str()) >= MAX_PENDING_HEIGHTS {
+            println!("waiting for sinks to accept sent heights! task_id={}, resume_height={}", sub_event.task_id, delivery::resume_height(sub_event.task_id.as_str(), sub_event.curr_height));
+            return Duration::from_millis(sub_event.poll_interval);
+        }
+        let now = timestamp_millis();
+        if !sub_event.select_node(now) {
+            return sub_event.retry_delay(now);
+        }
+        let result = Self::poll(sub_event).await;
+        if sub_event.status == SubscribeStatus::Error && sub_event.node_health[usize::from(sub_event.node_idx)].failures == 0 {
+            println!("node is recovered! task_id={}, node={}", sub_event.task_id, libs::node::redact_url(sub_event.nodes[usize::from(sub_event.node_idx)].as_str()));
+            sub_event.status = SubscribeStatus::Working;
+            Self::sync_event(&sinks.rocks, sub_event);
+        }
+        if matches!(result, Err(ExpectedError::BlockHeightError(_))) && sub_event.is_idle() && sub_event.nodes.len() > 1 {
+            Self::probe_heads(sub_event).await;
+        }
+        match result {
+            Ok(values) => {
+                Self::send(sub_event, &values, sinks);
+                Self::advance(&sinks.rocks, sub_event);
+                Duration::from_millis(0)
+            }
+            Err(ExpectedError::ReorgError(err_msg)) => {
+                println!("{}", err_msg);
+                match Self::rollback(sub_event, sinks).await {
+                    Ok(_) => Self::sync_event(&sinks.rocks, sub_event),
+                    Err(err) => sub_event.handle_error(&sinks.rocks, err.to_string()),
+                }
+                Duration::from_millis(0)
+            }
+            Err(err) => Self::error_handler(&sinks.rocks, sub_event, err)
+        }
+    }
+
+    /// sends the values of `curr_height` to every enabled sink, which waits for their acknowledgements before it is checkpointed.
+    fn send(sub_event: &SubscribeEvent, values: &[Value], sinks: &Sinks) {
+        let prefix = format!("{}::{}", A::CHAIN, sub_event.target.value());
+        let enabled = libs::callback::enabled_sinks(prefix.as_str(), !values.is_empty());
+        Self::deliver(sub_event.task_id.as_str(), sub_event.curr_height, values, enabled, A::table(&sub_event.target).as_str(), sinks);
+    }
+
+    /// a sink failing to take the values(ex. no schema of the table, a closed channel) keeps them as dead letters and accepts the height,
+    /// as no acknowledgement of the sink would come.
+    fn deliver(task_id: &str, height: u64, values: &[Value], enabled: Vec<String>, table: &str, sinks: &Sinks) {
+        delivery::expect(task_id, height, enabled.clone());
+        for sink in enabled.iter() {
+            let result = match sink.as_str() {
+                "mysql" => libs::callback::mysql(task_id, height, values, sinks.schema.get(table), &sinks.mysql),
+                "mongo" => libs::callback::mongo(task_id, height, values, table, sinks.schema.get(table), &sinks.mongo),
+                "rabbit" => libs::callback::rabbit(task_id, height, values, &sinks.rabbit),
+                _ => Ok(()),
+            };
+            if let Err(err) = result {
+                let letter = json!({"table": table, "values": values});
+                delivery::dead_letter(&sinks.rocks, sink, task_id, height, 0, letter.as_object().unwrap().clone(), err.to_string().as_str());
+                delivery::ack(task_id, height, sink);
+            }
+        }
+    }
+
+    pub fn shutdown(&mut self) {}
+
+    fn init(&mut self) {
+        self.sub_events = Some(Vec::new());
+        let channels = MultiChannel::new(vec!(A::CHAIN, "rocks", "mysql", "rabbit", "mongo"));
+        self.channels = Some(channels.to_owned());
+        self.monitor = Some(app::subscribe_channel(String::from(A::CHAIN)));
+        self.schema = Some(HashMap::new());
+    }
+
+    fn task_prefix() -> String {
+        format!("task:{}", A::CHAIN)
+    }
+
+    fn register_jsonrpc(&self) {
+        let plugin_handle = app::get_plugin::<JsonRpcPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let jsonrpc = plugin.downcast_mut::<JsonRpcPlugin>().unwrap();
+
+        let plugin_handle = app::get_plugin::<RocksPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let rocks = plugin.downcast_mut::<RocksPlugin>().unwrap();
+
+        let chain_channel = self.channels.as_ref().unwrap().get(A::CHAIN);
+        let rocks_db = rocks.get_db();
+        jsonrpc.add_method(format!("{}_subscribe", A::PREFIX), move |params: Params| {
+            let params: Map<String, Value> = params.parse().unwrap();
+            let verified = Self::verify_subscribe(&params);
+            if verified.is_err() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+            let task_id = SubscribeTask::task_id(A::CHAIN, &params);
+            let value = get_static(&rocks_db, task_id.as_str());
+            if value.is_null() {
+                let message = ChainMsg::new(ChainMethod::Subscribe, Value::Object(params.clone()));
+                let _ = chain_channel.send(message);
+
+                Box::new(futures::future::ok(Value::String(format!("subscription requested! task_id={}", task_id))))
+            } else {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(format!("already exist task! task_id={}", task_id)));
+                Box::new(futures::future::ok(Value::Object(error)))
+            }
+        });
+
+        let chain_channel = self.channels.as_ref().unwrap().get(A::CHAIN);
+        let rocks_db = rocks.get_db();
+        jsonrpc.add_method(format!("{}_unsubscribe", A::PREFIX), move |params: Params| {
+            let params: Map<String, Value> = params.parse().unwrap();
+            let verified = unsubscribe::verify(&params);
+            if verified.is_err() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+
+            let task_id = get_str(&params, "task_id").unwrap();
+            let value = get_static(&rocks_db, task_id);
+            if value.is_null() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(format!("task does not exist! task_id={}", task_id)));
+                Box::new(futures::future::ok(Value::Object(error)))
+            } else {
+                let message = ChainMsg::new(ChainMethod::Unsubscribe, Value::Object(params.clone()));
+                let _ = chain_channel.send(message);
+
+                Box::new(futures::future::ok(Value::String(format!("unsubscription requested! task_id={}", task_id))))
+            }
+        });
+
+        let chain_channel = self.channels.as_ref().unwrap().get(A::CHAIN);
+        let rocks_db = rocks.get_db();
+        jsonrpc.add_method(format!("{}_resubscribe", A::PREFIX), move |params: Params| {
+            let params: Map<String, Value> = params.parse().unwrap();
+            let verified = resubscribe::verify(&params);
+            if verified.is_err() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+
+            let task_id = get_str(&params, "task_id").unwrap();
+            let value = get_static(&rocks_db, task_id);
+            if value.is_null() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(format!("subscription does not exist! task_id={}", task_id)));
+                Box::new(futures::future::ok(Value::Object(error)))
+            } else {
+                let message = ChainMsg::new(ChainMethod::Resubscribe, Value::Object(params.clone()));
+                let _ = chain_channel.send(message);
+
+                Box::new(futures::future::ok(Value::String(format!("resubscription requested! task_id={}", task_id))))
+            }
+        });
+
+        let chain_channel = self.channels.as_ref().unwrap().get(A::CHAIN);
+        let rocks_db = rocks.get_db();
+        jsonrpc.add_method(format!("{}_stop_subscription", A::PREFIX), move |params: Params| {
+            let params: Map<String, Value> = params.parse().unwrap();
+            let verified = stop_subscribe::verify(&params);
+            if verified.is_err() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+
+            let task_id = get_str(&params, "task_id").unwrap();
+            let value = get_static(&rocks_db, task_id);
+            if value.is_null() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(format!("task does not exist! task_id={}", task_id)));
+                Box::new(futures::future::ok(Value::Object(error)))
+            } else {
+                let message = ChainMsg::new(ChainMethod::Stop, Value::Object(params.clone()));
+                let _ = chain_channel.send(message);
+
+                Box::new(futures::future::ok(Value::String(format!("stop subscription requested! task_id={}", task_id))))
+            }
+        });
+
+        let rocks_db = rocks.get_db();
+        jsonrpc.add_method(format!("{}_get_tasks", A::PREFIX), move |params: Params| {
+            let params: Map<String, Value> = params.parse().unwrap();
+            let verified = get_task::verify(&params);
+            if verified.is_err() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+
+            let prefix = match params.get("task_id") {
+                None => Self::task_prefix(),
+                Some(task_id) => String::from(task_id.as_str().unwrap()),
+            };
+            let mut tasks = get_by_prefix_static(&rocks_db, prefix.as_str());
+            let now = timestamp();
+            for task in tasks.as_array_mut().unwrap().iter_mut() {
+                let task_object = task.as_object_mut().unwrap();
+                SubscribeTask::redact(task_object);
+                if let Some(progress) = SubscribeTask::progress(task_object, now) {
+                    task_object.insert(String::from("progress"), progress);
+                }
+            }
+            Box::new(futures::future::ok(tasks))
+        });
+
+        jsonrpc.add_method(format!("{}_validate_filter", A::PREFIX), move |params: Params| {
+            let params: Map<String, Value> = params.parse().unwrap();
+            let verified = validate_filter::verify(&params);
+            if verified.is_err() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+
+            let strict_paths = get_bool(&params, "strict_paths").unwrap_or(false);
+            let filter = Filter::parse(get_str(&params, "filter").unwrap(), strict_paths).unwrap();
+            let sample = params.get("sample").unwrap();
+            let view = A::filter_view(sample).unwrap_or_else(|| { sample.clone() });
+            let (result, matched_paths) = filter.evaluate(view.as_object().unwrap()).unwrap();
+            Box::new(futures::future::ok(json!({ "result": result, "matched_paths": matched_paths })))
+        });
+    }
+
+    fn verify_subscribe(params: &Map<String, Value>) -> Result<(), ExpectedError> {
+        subscribe::verify(params)?;
+        let target = SubscribeTarget::find(get_str(params, "target")?).unwrap();
+        if !A::TARGETS.contains(&target) {
+            let targets: Vec<String> = A::TARGETS.iter().map(|target| { target.value() }).collect();
+            return Err(ExpectedError::InvalidError(format!("{} does not support {} target! target=[{}]", A::CHAIN, target.value(), targets.join(", "))));
+        }
+        if params.contains_key("websocket") && A::head_subscription().is_none() {
+            return Err(ExpectedError::InvalidError(format!("{} does not support websocket!", A::CHAIN)));
+        }
+        A::options(params)?;
+        Ok(())
+    }
+
+    /// schema of tables, which MongoDB also follows for the natural key of upserts.
+    fn load_schema(&mut self) {
+        let json_str = fs::read_to_string(A::SCHEMA_FILE).unwrap();
+        let json_schema: Value = serde_json::from_str(json_str.as_str()).unwrap();
+        let schema_map = json_schema.as_object().unwrap();
+
+        let schema = self.schema.as_mut().unwrap();
+        for (table, values) in schema_map {
+            let created_schema = Schema::from(table.clone(), values).unwrap();
+            schema.insert(table.clone(), created_schema);
+        }
+    }
+
+    fn init_mysql(&mut self) {
+        if let Some(state) = app::plugin_state::<MySqlPlugin>() {
+            if state != State::Initialized {
+                return;
+            }
+        }
+        let schema = self.schema.as_ref().unwrap();
+
+        let plugin_handle = app::get_plugin::<MySqlPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let mysql = plugin.downcast_mut::<MySqlPlugin>().unwrap();
+
+        for (_, selected_schema) in schema.iter() {
+            // a sink writing to a table without the schema columns would fail every block, so the app does not start.
+            if let Err(err) = mysql.migrate(selected_schema) {
+                if !mysql.is_dry_run() {
+                    panic!("mysql migration is failed! table={}, error={}", selected_schema.table, err.to_string());
+                }
+                println!("mysql migration is failed! table={}, error={}", selected_schema.table, err.to_string());
+            }
+        }
+
+        let plugin_handle = app::get_plugin::<JsonRpcPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let jsonrpc = plugin.downcast_mut::<JsonRpcPlugin>().unwrap();
+
+        let pool = mysql.get_pool();
+        jsonrpc.add_method(format!("{}_mysql_get_blocks", A::PREFIX), move |params: Params| {
+            let params: Map<String, Value> = params.parse().unwrap();
+            let verified = get_blocks::verify(&params);
+            if verified.is_err() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+            let order = get_str(&params, "order").unwrap();
+            let query = format!("select * from {}_block where {} >= :from_height and {} <= :to_height order by 1 {}", A::PREFIX, A::BLOCK_HEIGHT_COLUMN, A::BLOCK_HEIGHT_COLUMN, order);
+            let selected_params = select_value(&params, vec!["from_height", "to_height"]).unwrap();
+            let converted_params = selected_params.iter().map(|(k, v)| { (k.clone(), A::height_param(v.as_u64().unwrap())) }).collect();
+
+            let result = MySqlPlugin::query_static(&pool, query, get_params(&converted_params)).unwrap();
+            Box::new(futures::future::ok(Value::Array(result)))
+        });
+
+        let pool = mysql.get_pool();
+        jsonrpc.add_method(format!("{}_mysql_get_txs", A::PREFIX), move |params: Params| {
+            let params: Map<String, Value> = params.parse().unwrap();
+            let verified = get_txs::verify(&params);
+            if verified.is_err() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+            let (query, selected_params) = if params.get("txhash").is_some() {
+                let query = format!("select * from {}_tx where {}=:txhash", A::PREFIX, A::TX_HASH_COLUMN);
+                let selected_params = select_value(&params, vec!["txhash"]).unwrap();
+                (query, selected_params)
+            } else {
+                let order = get_str(&params, "order").unwrap();
+                let query = format!("select * from {}_tx where {} >= :from_height and {} <= :to_height order by 1 {}", A::PREFIX, A::TX_HEIGHT_COLUMN, A::TX_HEIGHT_COLUMN, order);
+                let selected_params = select_value(&params, vec!["from_height", "to_height"]).unwrap();
+                let converted_params = selected_params.iter().map(|(k, v)| { (k.clone(), A::height_param(v.as_u64().unwrap())) }).collect();
+
+                (query, converted_params)
+            };
+            let result = MySqlPlugin::query_static(&pool, query, get_params(&selected_params)).unwrap();
+            Box::new(futures::future::ok(Value::Array(result)))
+        });
+    }
+
+    fn load_tasks(&mut self) {
+        let plugin_handle = app::get_plugin::<RocksPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let rocks = plugin.downcast_mut::<RocksPlugin>().unwrap();
+
+        let rocks_db = rocks.get_db();
+        let raw_tasks = get_by_prefix_static(&rocks_db, Self::task_prefix().as_str());
+        let checkpoints = Self::mysql_checkpoints();
+        let sub_events = self.sub_events.as_mut().unwrap();
+        for raw_task in raw_tasks.as_array().unwrap() {
+            let task = raw_task.as_object().unwrap();
+            let mut sub_event = SubscribeEvent::from(task);
+            let mysql_sync = libs::opts::bool(format!("{}::{}-mysql-sync", A::CHAIN, sub_event.target.value()).as_str()).unwrap_or(false);
+            if let Some(checkpoint) = checkpoints.get(&sub_event.task_id).filter(|_| { mysql_sync }) {
+                if sub_event.rewind(*checkpoint + 1) {
+                    println!("task is rewound to the mysql checkpoint! task_id={}, curr_height={}", sub_event.task_id, sub_event.curr_height);
+                }
+            }
+            sub_events.push(sub_event);
+        }
+    }
+
+    /// heights written to MySQL by task id, so blocks lost on shutdown are written again. empty if MySQL is not used.
+    fn mysql_checkpoints() -> HashMap<String, u64> {
+        if app::plugin_state::<MySqlPlugin>() != Some(State::Initialized) {
+            return HashMap::new();
+        }
+        let plugin_handle = app::get_plugin::<MySqlPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let mysql = plugin.downcast_mut::<MySqlPlugin>().unwrap();
+        match MySqlPlugin::checkpoints_static(&mysql.get_pool()) {
+            Ok(checkpoints) => checkpoints.into_iter().collect(),
+            Err(err) => {
+                println!("failed to load mysql checkpoints! error={}", err.to_string());
+                HashMap::new()
+            }
+        }
+    }
+
+    /// takes the block of `curr_height` from the backfill buffer, which is filled by up to `concurrency` parallel fetches.
+    /// parent hashes are checked here in height order, so values are sent to the sinks in order.
+    async fn poll(sub_event: &mut SubscribeEvent) -> Result<Vec<Value>, ExpectedError> {
+        if !sub_event.backfill.contains_key(&sub_event.curr_height) {
+            Self::fill_backfill(sub_event).await?;
+        }
+        let (hashes, values) = opt_to_result(sub_event.backfill.remove(&sub_event.curr_height))?;
+        if let Some((hash, parent_hash)) = hashes {
+            if let Some(synced_hash) = sub_event.parent_hash() {
+                if *synced_hash != parent_hash {
+                    return Err(ExpectedError::ReorgError(format!("chain reorganization detected! height={}, parent_hash={}, synced_hash={}", sub_event.curr_height, parent_hash, synced_hash)));
+                }
+            }
+            sub_event.push_block_hash(sub_event.curr_height, hash);
+        }
+        if sub_event.target == SubscribeTarget::Block && values.is_empty() {
+            return Err(ExpectedError::FilterError(String::from("value does not match on filter condition!")));
+        }
+        Ok(values)
+    }
+
+    /// fetches heights from `curr_height` in parallel, spread across the available nodes starting from `node_idx`.
+    /// fetched heights are kept even if a lower one fails, and only the error of `curr_height` is returned.
+    async fn fill_backfill(sub_event: &mut SubscribeEvent) -> Result<(), ExpectedError> {
+        let node_idx = usize::from(sub_event.node_idx);
+        let node = sub_event.node_url(node_idx);
+        let concurrency = sub_event.concurrency.max(1);
+        if sub_event.curr_height > sub_event.confirmed_height {
+            let started_at = Instant::now();
+            let head_height = match A::head_height(sub_event, node.as_str()).await {
+                Ok(None) if concurrency > 1 => A::latest_height(sub_event, node.as_str()).await.map(Some),
+                head_height => head_height,
+            };
+            Self::record_request(sub_event, node_idx, started_at.elapsed(), &head_height);
+            let head_height = head_height?;
+            if let Some(head_height) = head_height {
+                sub_event.confirmed_height = head_height.saturating_sub(sub_event.confirmations);
+                if sub_event.curr_height > sub_event.confirmed_height {
+                    return Err(ExpectedError::BlockHeightError(format!("block has not yet been confirmed! height={}, confirmed_height={}", sub_event.curr_height, sub_event.confirmed_height)));
+                }
+            }
+        }
+
+        let mut last_height = sub_event.curr_height + concurrency - 1;
+        if sub_event.confirmed_height >= sub_event.curr_height {
+            last_height = last_height.min(sub_event.confirmed_height);
+        }
+        if let Some(end_height) = sub_event.end_height {
+            last_height = last_height.min(end_height);
+        }
+        let heights: Vec<u64> = (sub_event.curr_height..=last_height).filter(|height| { !sub_event.backfill.contains_key(height) }).collect();
+
+        let nodes = sub_event.poll_nodes(timestamp_millis());
+        let event: &SubscribeEvent = sub_event;
+        let fetches = heights.iter().enumerate().map(|(idx, height)| {
+            let node_idx = nodes[idx % nodes.len()];
+            let node = event.node_url(node_idx);
+            async move {
+                let started_at = Instant::now();
+                let result = Self::fetch(event, node.as_str(), *height).await;
+                (*height, node_idx, started_at.elapsed(), result)
+            }
+        });
+        let results: Vec<(u64, usize, Duration, Result<Fetched, ExpectedError>)> = join_all(fetches).await;
+
+        let mut curr_result = Ok(());
+        for (height, node_idx, latency, result) in results.into_iter() {
+            Self::record_request(sub_event, node_idx, latency, &result);
+            match result {
+                Ok(fetched) => {
+                    sub_event.backfill.insert(height, fetched);
+                }
+                Err(err) if height == sub_event.curr_height => curr_result = Err(err),
+                Err(_) => {}
+            }
+        }
+        curr_result
+    }
+
+    /// a failed request makes the node wait for retry. a block not yet created is not a failure of the node.
+    fn record_request<T>(sub_event: &mut SubscribeEvent, node_idx: usize, latency: Duration, result: &Result<T, ExpectedError>) {
+        let latency = latency.as_millis() as u64;
+        let health = &mut sub_event.node_health[node_idx];
+        match result {
+            Ok(_) | Err(ExpectedError::BlockHeightError(_)) => health.record_success(latency),
+            Err(_) => health.record_failure(latency, sub_event.poll_interval, timestamp_millis()),
+        }
+    }
+
+    /// asks every available node for its latest height, so lagging nodes are skipped by `select_node`.
+    async fn probe_heads(sub_event: &mut SubscribeEvent) {
+        let now = timestamp_millis();
+        let event: &SubscribeEvent = sub_event;
+        let probes = (0..event.nodes.len()).filter(|idx| { event.node_health[*idx].is_available(now) }).map(|idx| {
+            let node = event.node_url(idx);
+            async move {
+                let started_at = Instant::now();
+                let result = A::latest_height(event, node.as_str()).await;
+                (idx, started_at.elapsed(), result)
+            }
+        });
+        let results: Vec<(usize, Duration, Result<u64, ExpectedError>)> = join_all(probes).await;
+        for (idx, latency, result) in results.into_iter() {
+            if let Ok(head_height) = result {
+                sub_event.node_health[idx].record_head(head_height);
+            }
+            Self::record_request(sub_event, idx, latency, &result);
+        }
+    }
+
+    /// block hashes and filtered values of the height. an unmatched block gives empty values.
+    async fn fetch(sub_event: &SubscribeEvent, node: &str, height: u64) -> Result<Fetched, ExpectedError> {
+        let block = A::fetch_block(sub_event, node, height).await?;
+        let hashes = A::block_hashes(&block);
+        let values = match sub_event.target {
+            SubscribeTarget::Block => {
+                let record = A::block_record(&block)?;
+                if Self::matches(sub_event, &record)? {
+                    vec![record]
+                } else {
+                    Vec::new()
+                }
+            }
+            SubscribeTarget::Tx => {
+                let txs = A::fetch_txs(sub_event, node, height, &block).await?;
+                Self::filter_values(sub_event, txs)?
+            }
+            _ => {
+                let values = A::fetch_target(sub_event, node, height, &block).await?;
+                Self::filter_values(sub_event, values)?
+            }
+        };
+        Ok((hashes, values))
+    }
+
+    /// walks back to the common ancestor, then deletes values of orphaned blocks and publishes revert messages.
+    async fn rollback(sub_event: &mut SubscribeEvent, sinks: &Sinks) -> Result<(), ExpectedError> {
+        let node = sub_event.node_url(usize::from(sub_event.node_idx));
+        let event: &SubscribeEvent = sub_event;
+        let orphans = Self::find_orphans(&event.block_hashes, |height| {
+            let node = node.clone();
+            async move {
+                let block = A::fetch_block(event, node.as_str(), height).await?;
+                let (hash, _) = opt_to_result(A::block_hashes(&block))?;
+                Ok(hash)
+            }
+        }).await?;
+
+        let table = A::table(&sub_event.target);
+        let prefix = format!("{}::{}", A::CHAIN, sub_event.target.value());
+        for (height, hash) in orphans.iter() {
+            if let Some(column) = A::block_hash_column(&sub_event.target) {
+                if let Err(err) = libs::callback::mysql_revert(prefix.clone(), sub_event.task_id.as_str(), table.as_str(), column, hash, &sinks.mysql) {
+                    println!("{}", err.to_string());
+                }
+                if let Err(err) = libs::callback::mongo_revert(prefix.clone(), sub_event.task_id.as_str(), table.as_str(), column, hash, &sinks.mongo) {
+                    println!("{}", err.to_string());
+                }
+            }
+            let revert = json!({
+                "method": "revert",
+                "chain": A::CHAIN,
+                "target": sub_event.target.value(),
+                "sub_id": sub_event.sub_id,
+                "height": height,
+                "block_hash": hash
+            });
+            if let Err(err) = libs::callback::rabbit_revert(prefix.clone(), sub_event.task_id.as_str(), &revert, &sinks.rabbit) {
+                println!("{}", err.to_string());
+            }
+        }
+
+        sub_event.backfill.clear();
+        let ancestor_idx = sub_event.block_hashes.len() - orphans.len();
+        sub_event.block_hashes.truncate(ancestor_idx);
+        if let Some((height, _)) = orphans.last() {
+            sub_event.curr_height = *height;
+            if let Err(err) = libs::callback::mysql_rewind(prefix, sub_event.task_id.as_str(), height.saturating_sub(1), &sinks.mysql) {
+                println!("{}", err.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// synced blocks whose hash differs from the canonical one, from the highest.
+    async fn find_orphans<F, Fut>(block_hashes: &[(u64, String)], canonical_hash: F) -> Result<Vec<(u64, String)>, ExpectedError>
+        where F: Fn(u64) -> Fut, Fut: Future<Output = Result<String, ExpectedError>> {
+        let mut orphans: Vec<(u64, String)> = Vec::new();
+        for (height, hash) in block_hashes.iter().rev() {
+            if canonical_hash(*height).await? == *hash {
+                return Ok(orphans);
+            }
+            orphans.push((*height, hash.clone()));
+        }
+        Err(ExpectedError::ReorgError(format!("common ancestor does not exist in recent {} blocks!", block_hashes.len())))
+    }
+
+    fn filter_values(sub_event: &SubscribeEvent, values: Vec<Value>) -> Result<Vec<Value>, ExpectedError> {
+        let mut filtered_values: Vec<Value> = Vec::new();
+        for value in values.into_iter() {
+            if Self::matches(sub_event, &value)? {
+                filtered_values.push(value);
+            }
+        }
+        Ok(filtered_values)
+    }
+
+    fn matches(sub_event: &SubscribeEvent, value: &Value) -> Result<bool, ExpectedError> {
+        if let Filter::All = sub_event.filter_expr {
+            return Ok(true);
+        }
+        match A::filter_view(value) {
+            Some(view) => sub_event.filter_expr.matches(opt_ref_to_result(view.as_object())?),
+            None => sub_event.filter_expr.matches(opt_ref_to_result(value.as_object())?),
+        }
+    }
+
+    fn sync_event(rocks_channel: &channel::Sender, sub_event: &mut SubscribeEvent) {
+        let task = SubscribeTask::from(&sub_event, String::from(""));
+        let task_id = task.task_id.clone();
+
+        let msg = RocksMsg::new(RocksMethod::Put, task_id, Value::String(json!(task).to_string()));
+        let _ = rocks_channel.send(msg);
+    }
+
+    /// moves to the next height and completes the subscription after `end_height`.
+    /// the next height is saved, so a restart resumes after the height just processed unless a sink has not yet accepted it.
+    fn advance(rocks_channel: &channel::Sender, sub_event: &mut SubscribeEvent) {
+        sub_event.curr_height += 1;
+        sub_event.idle_polls = 0;
+        if sub_event.is_completed() {
+            sub_event.status = SubscribeStatus::Completed;
+        }
+        Self::sync_event(rocks_channel, sub_event);
+    }
+
+    /// returns the delay before the next poll. it backs off while the head is reached.
+    fn error_handler(rocks_channel: &channel::Sender, sub_event: &mut SubscribeEvent, error: ExpectedError) -> Duration {
+        match error {
+            ExpectedError::BlockHeightError(err_msg) => {
+                println!("{}", err_msg);
+                sub_event.backoff()
+            }
+            ExpectedError::FilterError(err_msg) => {
+                println!("{}", err_msg);
+                Self::advance(&rocks_channel, sub_event);
+                Duration::from_millis(0)
+            }
+            _ => {
+                sub_event.handle_error(&rocks_channel, error.to_string());
+                Duration::from_millis(sub_event.poll_interval)
+            }
+        }
+    }
+
+    fn message_handler(msg: &Value, controls: &mut Controls, sinks: &Sinks) {
+        let parsed_msg = msg.as_object().unwrap();
+        let method = ChainMethod::find(get_str(parsed_msg, "method").unwrap()).unwrap();
+        let params = get_object(parsed_msg, "value").unwrap();
+        match method {
+            ChainMethod::Subscribe => {
+                let mut new_event = SubscribeEvent::new(A::CHAIN, &params);
+                new_event.options = A::options(&params).unwrap();
+
+                let task = SubscribeTask::from(&new_event, String::from(""));
+                let msg = RocksMsg::new(RocksMethod::Put, new_event.task_id.clone(), Value::String(json!(task).to_string()));
+                let _ = sinks.rocks.send(msg);
+
+                Self::run(new_event, sinks, controls);
+            }
+            _ => {
+                let task_id = get_string(&params, "task_id").unwrap();
+                let control = if method == ChainMethod::Unsubscribe { controls.remove(&task_id) } else { controls.get(&task_id).cloned() };
+                if let Some(control) = control {
+                    let _ = control.send(method);
+                }
+            }
+        };
+    }
+
+    /// returns false if the subscription loop has to finish.
+    fn control_handler(method: ChainMethod, sub_event: &mut SubscribeEvent, sinks: &Sinks) -> bool {
+        match method {
+            ChainMethod::Unsubscribe => {
+                let msg = RocksMsg::new(RocksMethod::Delete, sub_event.task_id.clone(), Value::Null);
+                let _ = sinks.rocks.send(msg);
+                delivery::clear(sub_event.task_id.as_str());
+                let prefix = format!("{}::{}", A::CHAIN, sub_event.target.value());
+                if let Err(err) = libs::callback::mysql_checkpoint_delete(prefix, sub_event.task_id.as_str(), &sinks.mysql) {
+                    println!("{}", err.to_string());
+                }
+                return false;
+            }
+            ChainMethod::Resubscribe => {
+                // heights never accepted by the sinks are sent again.
+                let resume_height = delivery::resume_height(sub_event.task_id.as_str(), sub_event.curr_height);
+                if sub_event.rewind(resume_height) {
+                    println!("task is rewound to the undelivered height! task_id={}, curr_height={}", sub_event.task_id, sub_event.curr_height);
+                }
+                delivery::clear(sub_event.task_id.as_str());
+                sub_event.node_idx = 0;
+                for health in sub_event.node_health.iter_mut() {
+                    health.retry_at = 0;
+                }
+                sub_event.idle_polls = 0;
+                sub_event.status = if sub_event.is_completed() { SubscribeStatus::Completed } else { SubscribeStatus::Working };
+            }
+            ChainMethod::Stop => {
+                sub_event.status = SubscribeStatus::Stopped;
+            }
+            ChainMethod::Subscribe => {}
+        };
+        Self::sync_event(&sinks.rocks, sub_event);
+        true
+    }
+}
+
+#[cfg(test)]
+mod chain_test {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+    use tokio::sync::broadcast;
+
+    use crate::error::error::ExpectedError;
+    use crate::libs::delivery;
+    use crate::libs::mock;
+    use crate::plugin::chain::{ChainAdapter, ChainPlugin, Sinks};
+    use crate::plugin::ethereum::Ethereum;
+    use crate::types::subscribe::{SubscribeEvent, SubscribeTarget};
+
+    /// chain of 10 blocks. "flaky" node fails on height 2.
+    struct MockChain;
+
+    #[async_trait]
+    impl ChainAdapter for MockChain {
+        const CHAIN: &'static str = "mock";
+        const PREFIX: &'static str = "mock";
+        const SCHEMA_FILE: &'static str = "";
+        const BLOCK_HEIGHT_COLUMN: &'static str = "height";
+        const TX_HEIGHT_COLUMN: &'static str = "height";
+        const TX_HASH_COLUMN: &'static str = "hash";
+        const TARGETS: &'static [SubscribeTarget] = &[SubscribeTarget::Block];
+
+        async fn latest_height(_: &SubscribeEvent, _: &str) -> Result<u64, ExpectedError> {
+            Ok(10)
+        }
+
+        async fn fetch_block(_: &SubscribeEvent, node: &str, height: u64) -> Result<Value, ExpectedError> {
+            if height > 10 {
+                return Err(ExpectedError::BlockHeightError(String::from("block has not yet been created!")));
+            }
+            if node == "flaky" && height == 2 {
+                return Err(ExpectedError::RequestError(String::from("connection reset!")));
+            }
+            Ok(json!({"height": height, "hash": format!("h{}", height), "parent": format!("h{}", height - 1)}))
+        }
+
+        async fn fetch_txs(_: &SubscribeEvent, _: &str, _: u64, _: &Value) -> Result<Vec<Value>, ExpectedError> {
+            Ok(Vec::new())
+        }
+
+        fn block_hashes(block: &Value) -> Option<(String, String)> {
+            Some((block["hash"].as_str()?.to_string(), block["parent"].as_str()?.to_string()))
+        }
+    }
+
+    fn block_hashes() -> Vec<(u64, String)> {
+        (10..15).map(|height| { (height, format!("0x{:x}", height)) }).collect()
+    }
+
+    fn sub_event(sub_id: &str, start_height: u64) -> SubscribeEvent {
+        mock::sub_event("mock", json!({"sub_id": sub_id, "start_height": start_height, "nodes": ["stable", "flaky"]}))
+    }
+
+    #[tokio::test]
+    async fn find_orphans_test() {
+        let orphans = ChainPlugin::<Ethereum>::find_orphans(&block_hashes(), |height| async move {
+            if height >= 13 { Ok(format!("0x{:x}'", height)) } else { Ok(format!("0x{:x}", height)) }
+        }).await.unwrap();
+
+        assert_eq!(orphans, vec![(14, String::from("0xe")), (13, String::from("0xd"))]);
+    }
+
+    #[tokio::test]
+    async fn find_orphans_too_deep_test() {
+        let result = ChainPlugin::<Ethereum>::find_orphans(&block_hashes(), |height| async move { Ok(format!("0x{:x}'", height)) }).await;
+
+        assert!(matches!(result, Err(ExpectedError::ReorgError(_))));
+    }
+
+    #[tokio::test]
+    async fn poll_backfill_order_test() {
+        let mut sub_event = sub_event("mocknet", 1);
+        sub_event.end_height = Some(4);
+        sub_event.concurrency = 4;
+
+        let mut heights: Vec<Value> = Vec::new();
+        while !sub_event.is_completed() {
+            let values = ChainPlugin::<MockChain>::poll(&mut sub_event).await.unwrap();
+            heights.push(values[0]["height"].clone());
+            assert!(sub_event.backfill.keys().all(|height| { *height > sub_event.curr_height }));
+            sub_event.curr_height += 1;
+        }
+
+        assert_eq!(heights, vec![json!(1), json!(2), json!(3), json!(4)]);
+        assert_eq!(sub_event.block_hashes.last().unwrap(), &(4, String::from("h4")));
+    }
+
+    #[test]
+    fn deliver_without_schema_test() {
+        let sinks = Sinks {
+            rocks: broadcast::channel(16).0,
+            mysql: broadcast::channel(16).0,
+            mongo: broadcast::channel(16).0,
+            rabbit: broadcast::channel(16).0,
+            schema: Arc::new(HashMap::new()),
+        };
+        let task_id = "task:mock:block:no_schema";
+        ChainPlugin::<MockChain>::deliver(task_id, 1, &[json!({"height": 1})], vec![String::from("mysql")], "mock_block", &sinks);
+
+        assert_eq!((delivery::pending(task_id), delivery::resume_height(task_id, 2)), (0, 2));
+        delivery::clear(task_id);
+    }
+}
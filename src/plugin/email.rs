@@ -8,6 +8,7 @@ use crate::error::error::ExpectedError;
 use crate::libs;
 use crate::libs::serde::get_str;
 use crate::message;
+use crate::types::channel::next_message;
 
 pub struct EmailPlugin {
     monitor: Option<channel::Receiver>,
@@ -46,7 +47,7 @@ impl Plugin for EmailPlugin {
 impl EmailPlugin {
     fn recv(mut monitor: channel::Receiver, app: QuitHandle) {
         app::spawn(async move {
-            if let Ok(msg) = monitor.try_recv() {
+            while let Some(msg) = next_message(&mut monitor).await {
                 let parsed_msg = msg.as_object().unwrap();
 
                 let to = get_str(parsed_msg, "to").unwrap();
@@ -56,9 +57,9 @@ impl EmailPlugin {
                 if let Err(result) = Self::send(to, subject, body) {
                     println!("{}", result);
                 }
-            }
-            if !app.is_quiting() {
-                Self::recv(monitor, app);
+                if app.is_quiting() {
+                    break;
+                }
             }
         });
     }
@@ -1,46 +1,27 @@
-use std::collections::HashMap;
-use std::fs;
-use std::sync::Arc;
-
 use appbase::*;
-use appbase::channel::Sender;
-use appbase::plugin::State;
-use futures::lock::Mutex as FutureMutex;
-use jsonrpc_core::Params;
-use serde::{Deserialize, Serialize};
+use async_trait::async_trait;
 use serde_json::{json, Map, Value};
 
-use crate::{enumeration, libs, message};
 use crate::error::error::ExpectedError;
-use crate::libs::mysql::get_params;
-use crate::libs::opts::{opt_to_result, opt_ref_to_result};
+use crate::libs::opts::{opt_ref_to_result, opt_to_result};
 use crate::libs::request;
-use crate::libs::rocks::{get_by_prefix_static, get_static};
-use crate::libs::serde::{get_array, get_object, get_str, get_string, select_value};
+use crate::libs::serde::{get_array, get_str, get_string};
+use crate::libs::token;
+use crate::plugin::chain::{ChainAdapter, ChainPlugin};
 use crate::plugin::jsonrpc::JsonRpcPlugin;
-use crate::plugin::mysql::MySqlPlugin;
-use crate::plugin::rocks::{RocksMethod, RocksMsg, RocksPlugin};
-use crate::types::channel::MultiChannel;
+use crate::plugin::node::NodePlugin;
+use crate::plugin::rocks::RocksPlugin;
 use crate::types::enumeration::Enumeration;
-use crate::types::mysql::Schema;
-use crate::types::subscribe::{SubscribeEvent, SubscribeStatus, SubscribeTarget, SubscribeTask};
-use crate::validation::{get_blocks, get_task, get_txs, resubscribe, stop_subscribe, subscribe, unsubscribe};
+use crate::types::subscribe::{SubscribeEvent, SubscribeTarget};
+use crate::validation::eth_subscribe;
 
 pub struct EthereumPlugin {
-    sub_events: Option<SubscribeEvents>,
-    channels: Option<MultiChannel>,
-    monitor: Option<channel::Receiver>,
-    schema: Option<HashMap<String, Schema>>,
+    chain: ChainPlugin<Ethereum>,
 }
 
-const CHAIN: &str = "ethereum";
-const TASK_PREFIX: &str = "task:ethereum";
-
-type SubscribeEvents = Arc<FutureMutex<HashMap<String, SubscribeEvent>>>;
-
-message!((EthereumMsg; {value: Value}); (EthereumMethod; {Subscribe: "subscribe"}, {Resubscribe: "resubscribe"}, {Stop: "stop"}, {Unsubscribe: "unsubscribe"}));
+pub struct Ethereum;
 
-plugin::requires!(EthereumPlugin; JsonRpcPlugin, RocksPlugin);
+plugin::requires!(EthereumPlugin; JsonRpcPlugin, RocksPlugin, NodePlugin);
 
 impl Plugin for EthereumPlugin {
     fn new() -> Self {
@@ -48,442 +29,268 @@ impl Plugin for EthereumPlugin {
         app::arg(clap::Arg::new("ethereum::tx-mysql-sync").long("eth-tx-mysql-sync"));
         app::arg(clap::Arg::new("ethereum::block-mongo-sync").long("eth-block-mongo-sync"));
         app::arg(clap::Arg::new("ethereum::tx-mongo-sync").long("eth-tx-mongo-sync"));
-        app::arg(clap::Arg::new("ethereum::block-rabbit-mq-sync").long("eth-block-rabbit-mq-sync"));
-        app::arg(clap::Arg::new("ethereum::tx-rabbit-mq-sync").long("eth-tx-rabbit-mq-sync"));
+        app::arg(clap::Arg::new("ethereum::block-rabbit-mq-publish").long("eth-block-rabbit-mq-publish"));
+        app::arg(clap::Arg::new("ethereum::tx-rabbit-mq-publish").long("eth-tx-rabbit-mq-publish"));
+        app::arg(clap::Arg::new("ethereum::log-mysql-sync").long("eth-log-mysql-sync"));
+        app::arg(clap::Arg::new("ethereum::log-mongo-sync").long("eth-log-mongo-sync"));
+        app::arg(clap::Arg::new("ethereum::log-rabbit-mq-publish").long("eth-log-rabbit-mq-publish"));
+        app::arg(clap::Arg::new("ethereum::token_transfer-mysql-sync").long("eth-token-transfer-mysql-sync"));
+        app::arg(clap::Arg::new("ethereum::token_transfer-mongo-sync").long("eth-token-transfer-mongo-sync"));
+        app::arg(clap::Arg::new("ethereum::token_transfer-rabbit-mq-publish").long("eth-token-transfer-rabbit-mq-publish"));
 
         EthereumPlugin {
-            sub_events: None,
-            channels: None,
-            monitor: None,
-            schema: None,
+            chain: ChainPlugin::new(),
         }
     }
 
     fn initialize(&mut self) {
-        self.init();
-        self.register_jsonrpc();
-        self.init_mysql();
-        self.load_tasks();
+        self.chain.initialize();
     }
 
     fn startup(&mut self) {
-        let mut monitor = self.monitor.take().unwrap();
-        let sub_events = Arc::clone(self.sub_events.as_ref().unwrap());
-
-        let mut rocks_channel = self.channels.as_ref().unwrap().get("rocks");
-        let mysql_channel = self.channels.as_ref().unwrap().get("mysql");
-        let rabbit_channel = self.channels.as_ref().unwrap().get("rabbit");
-        let mongo_channel = self.channels.as_ref().unwrap().get("mongo");
-        let app = app::quit_handle().unwrap();
-
-        let schema = self.schema.as_ref().unwrap().clone();
-
-        app::spawn_blocking(move || {
-            loop {
-                if app.is_quiting() {
-                    break;
-                }
-                let sub_events_try_lock = sub_events.try_lock();
-                if sub_events_try_lock.is_none() {
-                    continue;
-                }
-                let mut sub_events_lock = sub_events_try_lock.unwrap();
-                if let Ok(msg) = monitor.try_recv() {
-                    Self::message_handler(&msg, &mut sub_events_lock, &mut rocks_channel);
-                }
+        self.chain.startup();
+    }
 
-                for (_, sub_event) in sub_events_lock.iter_mut() {
-                    if sub_event.is_workable() {
-                        if sub_event.target == SubscribeTarget::Block {
-                            let block_result = Self::poll_block(sub_event);
-                            match block_result {
-                                Ok(block) => {
-                                    // println!("event_id={}, block={}", sub_event.event_id(), block.to_string());
-
-                                    let schema_opt = schema.get("eth_block");
-                                    let prefix = format!("{}::{}", CHAIN, sub_event.target.value());
-                                    if let Err(err) = libs::callback::mysql(prefix.clone(), &block, schema_opt, &mysql_channel) {
-                                        println!("{}", err.to_string());
-                                    };
-                                    if let Err(err) = libs::callback::mongo(prefix.clone(), &block, "eth_block", &mongo_channel) {
-                                        println!("{}", err.to_string());
-                                    };
-                                    if let Err(err) = libs::callback::rabbit(prefix, &block, &rabbit_channel) {
-                                        println!("{}", err.to_string());
-                                    };
-
-                                    Self::sync_event(&rocks_channel, sub_event);
-                                    sub_event.curr_height += 1;
-                                }
-                                Err(err) => Self::error_handler(&rocks_channel, sub_event, err)
-                            }
-                        } else {
-                            let txs_result = Self::poll_txs(sub_event);
-                            match txs_result {
-                                Ok(txs) => {
-                                    for tx in txs {
-                                        let tx_object = tx.as_object().unwrap();
-                                        let filtered_result = libs::serde::filter(tx_object, sub_event.filter.clone());
-                                        if filtered_result.is_err() {
-                                            let err = filtered_result.unwrap_err();
-                                            sub_event.handle_error(&rocks_channel, err.to_string());
-                                            continue;
-                                        } else if filtered_result.is_ok() && !filtered_result.unwrap() {
-                                            continue;
-                                        }
-
-                                        // println!("event_id={}, tx={}", sub_event.event_id(), tx.to_string());
-
-                                        let schema_opt = schema.get("eth_tx");
-                                        let prefix = format!("{}::{}", CHAIN, sub_event.target.value());
-                                        if let Err(err) = libs::callback::mysql(prefix.clone(), &tx, schema_opt, &mysql_channel) {
-                                            println!("{}", err.to_string());
-                                        };
-                                        if let Err(err) = libs::callback::mongo(prefix.clone(), &tx, "eth_tx", &mongo_channel) {
-                                            println!("{}", err.to_string());
-                                        };
-                                        if let Err(err) = libs::callback::rabbit(prefix, &tx, &rabbit_channel) {
-                                            println!("{}", err.to_string());
-                                        };
-                                    }
-
-                                    Self::sync_event(&rocks_channel, sub_event);
-                                    sub_event.curr_height += 1;
-                                }
-                                Err(err) => Self::error_handler(&rocks_channel, sub_event, err)
-                            }
-                        }
-                    }
-                }
-            }
-        });
+    fn shutdown(&mut self) {
+        self.chain.shutdown();
     }
-    fn shutdown(&mut self) {}
 }
 
-impl EthereumPlugin {
-    fn init(&mut self) {
-        self.sub_events = Some(Arc::new(FutureMutex::new(HashMap::new())));
-        let channels = MultiChannel::new(vec!("ethereum", "rocks", "mysql", "rabbit", "mongo"));
-        self.channels = Some(channels.to_owned());
-        self.monitor = Some(app::subscribe_channel(String::from("ethereum")));
-        self.schema = Some(HashMap::new());
+#[async_trait]
+impl ChainAdapter for Ethereum {
+    const CHAIN: &'static str = "ethereum";
+    const PREFIX: &'static str = "eth";
+    const SCHEMA_FILE: &'static str = "schema/eth_mysql.json";
+    const BLOCK_HEIGHT_COLUMN: &'static str = "number";
+    const TX_HEIGHT_COLUMN: &'static str = "blockNumber";
+    const TX_HASH_COLUMN: &'static str = "hash";
+    const TARGETS: &'static [SubscribeTarget] = &[SubscribeTarget::Block, SubscribeTarget::Tx, SubscribeTarget::Log, SubscribeTarget::TokenTransfer];
+
+    async fn latest_height(_: &SubscribeEvent, node: &str) -> Result<u64, ExpectedError> {
+        let result = Self::call(node, "eth_blockNumber", json!([])).await?;
+        let hex_height = opt_to_result(result.as_str())?;
+        let height = u64::from_str_radix(hex_height.trim_start_matches("0x"), 16)?;
+        Ok(height)
     }
 
-    fn register_jsonrpc(&self) {
-        let plugin_handle = app::get_plugin::<JsonRpcPlugin>();
-        let mut plugin = plugin_handle.lock().unwrap();
-        let jsonrpc = plugin.downcast_mut::<JsonRpcPlugin>().unwrap();
-
-        let plugin_handle = app::get_plugin::<RocksPlugin>();
-        let mut plugin = plugin_handle.lock().unwrap();
-        let rocks = plugin.downcast_mut::<RocksPlugin>().unwrap();
-
-        let eth_channel = self.channels.as_ref().unwrap().get("ethereum");
-        let rocks_db = rocks.get_db();
-
-        jsonrpc.add_method(String::from("eth_subscribe"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = subscribe::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
-            }
-            let task_id = SubscribeTask::task_id(CHAIN, &params);
-            let value = get_static(&rocks_db, task_id.as_str());
-            if value.is_null() {
-                let message = EthereumMsg::new(EthereumMethod::Subscribe, Value::Object(params.clone()));
-                let _ = eth_channel.send(message);
-
-                Box::new(futures::future::ok(Value::String(format!("subscription requested! task_id={}", task_id))))
-            } else {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(format!("already exist task! task_id={}", task_id)));
-                Box::new(futures::future::ok(Value::Object(error)))
-            }
-        });
-
-        let eth_channel = self.channels.as_ref().unwrap().get("ethereum");
-        let rocks_db = rocks.get_db();
-        jsonrpc.add_method(String::from("eth_unsubscribe"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = unsubscribe::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
+    /// follows the `block_tag` option(safe, finalized) if it is set.
+    async fn head_height(sub_event: &SubscribeEvent, node: &str) -> Result<Option<u64>, ExpectedError> {
+        match sub_event.options.get("block_tag").and_then(|value| { value.as_str() }) {
+            Some(block_tag) => {
+                let block = Self::call(node, "eth_getBlockByNumber", json!([ block_tag, false ])).await?;
+                let block_object = opt_ref_to_result(block.as_object())?;
+                let height = u64::from_str_radix(get_str(block_object, "number")?.trim_start_matches("0x"), 16)?;
+                Ok(Some(height))
             }
+            None if sub_event.confirmations == 0 => Ok(None),
+            None => Ok(Some(Self::latest_height(sub_event, node).await?)),
+        }
+    }
 
-            let task_id = get_str(&params, "task_id").unwrap();
-            let value = get_static(&rocks_db, task_id);
-            if value.is_null() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(format!("task does not exist! task_id={}", task_id)));
-                Box::new(futures::future::ok(Value::Object(error)))
-            } else {
-                let eth_msg = EthereumMsg::new(EthereumMethod::Unsubscribe, Value::Object(params.clone()));
-                let _ = eth_channel.send(eth_msg);
-
-                Box::new(futures::future::ok(Value::String(format!("unsubscription requested! task_id={}", task_id))))
-            }
-        });
-
-        let eth_channel = self.channels.as_ref().unwrap().get("ethereum");
-        let rocks_db = rocks.get_db();
-        jsonrpc.add_method(String::from("eth_resubscribe"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = resubscribe::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
-            }
+    async fn fetch_block(sub_event: &SubscribeEvent, node: &str, height: u64) -> Result<Value, ExpectedError> {
+        let hex_height = format!("0x{:X}", height);
+        let full_txs = !matches!(sub_event.target, SubscribeTarget::Log | SubscribeTarget::TokenTransfer);
+        let block = Self::call(node, "eth_getBlockByNumber", json!([ hex_height, full_txs ])).await?;
+        if block.is_null() {
+            return Err(ExpectedError::BlockHeightError(String::from("block has not yet been created!")));
+        }
+        Ok(block)
+    }
 
-            let task_id = get_str(&params, "task_id").unwrap();
-            let value = get_static(&rocks_db, task_id);
-            if value.is_null() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(format!("subscription does not exist! task_id={}", task_id)));
-                Box::new(futures::future::ok(Value::Object(error)))
-            } else {
-                let message = EthereumMsg::new(EthereumMethod::Resubscribe, Value::Object(params.clone()));
-                let _ = eth_channel.send(message);
-
-                Box::new(futures::future::ok(Value::String(format!("resubscription requested! task_id={}", task_id))))
-            }
-        });
+    async fn fetch_txs(sub_event: &SubscribeEvent, node: &str, _: u64, block: &Value) -> Result<Vec<Value>, ExpectedError> {
+        let block_object = opt_ref_to_result(block.as_object())?;
+        let transactions = get_array(block_object, "transactions")?;
+        let with_receipts = sub_event.options.get("with_receipts").and_then(|value| { value.as_bool() }).unwrap_or(false);
+        if !with_receipts || transactions.is_empty() {
+            return Ok(transactions.clone());
+        }
+        let receipts = Self::fetch_receipts(node, block_object, transactions).await?;
+        Self::merge_receipts(transactions, &receipts)
+    }
 
-        let eth_channel = self.channels.as_ref().unwrap().get("ethereum");
-        let rocks_db = rocks.get_db();
-        jsonrpc.add_method(String::from("eth_stop_subscription"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = stop_subscribe::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
-            }
+    async fn fetch_target(sub_event: &SubscribeEvent, node: &str, _: u64, block: &Value) -> Result<Vec<Value>, ExpectedError> {
+        match sub_event.target {
+            SubscribeTarget::Log => Self::fetch_logs(sub_event, node, block).await,
+            SubscribeTarget::TokenTransfer => Self::fetch_token_transfers(sub_event, node, block).await,
+            _ => Err(ExpectedError::InvalidError(format!("{} target is not supported!", sub_event.target.value()))),
+        }
+    }
 
-            let task_id = get_str(&params, "task_id").unwrap();
-            let value = get_static(&rocks_db, task_id);
-            if value.is_null() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(format!("task does not exist! task_id={}", task_id)));
-                Box::new(futures::future::ok(Value::Object(error)))
-            } else {
-                let eth_msg = EthereumMsg::new(EthereumMethod::Stop, Value::Object(params.clone()));
-                let _ = eth_channel.send(eth_msg);
-
-                Box::new(futures::future::ok(Value::String(format!("stop subscription requested! task_id={}", task_id))))
+    fn options(params: &Map<String, Value>) -> Result<Map<String, Value>, ExpectedError> {
+        eth_subscribe::verify(params)?;
+        let mut options = Map::new();
+        for name in ["address", "topics", "with_receipts", "block_tag"].iter() {
+            if let Some(value) = params.get(*name) {
+                options.insert(String::from(*name), value.clone());
             }
-        });
+        }
+        Ok(options)
+    }
 
-        let rocks_db = rocks.get_db();
-        jsonrpc.add_method(String::from("eth_get_tasks"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = get_task::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
-            }
+    fn head_subscription() -> Option<Value> {
+        Some(json!({"jsonrpc": "2.0", "id": 1, "method": "eth_subscribe", "params": ["newHeads"]}))
+    }
 
-            let prefix = match params.get("task_id") {
-                None => TASK_PREFIX,
-                Some(task_id) => task_id.as_str().unwrap(),
-            };
-            let tasks = get_by_prefix_static(&rocks_db, prefix);
-            Box::new(futures::future::ok(tasks))
-        });
+    fn head_notification(message: &Value) -> Option<u64> {
+        if message.get("method")?.as_str()? != "eth_subscription" {
+            return None;
+        }
+        let number = message.pointer("/params/result/number")?.as_str()?;
+        u64::from_str_radix(number.trim_start_matches("0x"), 16).ok()
     }
 
-    fn load_tasks(&self) {
-        let plugin_handle = app::get_plugin::<RocksPlugin>();
-        let mut plugin = plugin_handle.lock().unwrap();
-        let rocks = plugin.downcast_mut::<RocksPlugin>().unwrap();
-
-        let rocks_db = rocks.get_db();
-        let raw_tasks = get_by_prefix_static(&rocks_db, TASK_PREFIX);
-        let sub_events = Arc::clone(self.sub_events.as_ref().unwrap());
-        raw_tasks.as_array().unwrap().iter()
-            .for_each(|raw_task| {
-                let task = raw_task.as_object().unwrap();
-                let event = SubscribeEvent::from(task);
-                let mut sub_events_lock = sub_events.try_lock().unwrap();
-                sub_events_lock.insert(event.task_id.clone(), event);
-            });
+    fn block_hashes(block: &Value) -> Option<(String, String)> {
+        let block_object = block.as_object()?;
+        Some((get_string(block_object, "hash").ok()?, get_string(block_object, "parentHash").ok()?))
     }
 
-    fn sync_event(rocks_channel: &channel::Sender, sub_event: &mut SubscribeEvent) {
-        let task = SubscribeTask::from(&sub_event, String::from(""));
-        let task_id = task.task_id.clone();
+    fn block_hash_column(target: &SubscribeTarget) -> Option<&'static str> {
+        match target {
+            SubscribeTarget::Block => Some("hash"),
+            SubscribeTarget::TokenTransfer => Some("block_hash"),
+            _ => Some("blockHash"),
+        }
+    }
 
-        let msg = RocksMsg::new(RocksMethod::Put, task_id, Value::String(json!(task).to_string()));
-        let _ = rocks_channel.send(msg);
+    fn height_param(height: u64) -> Value {
+        Value::String(format!("0x{:x}", height))
     }
+}
 
-    fn error_handler(rocks_channel: &channel::Sender, sub_event: &mut SubscribeEvent, error: ExpectedError) {
-        match error {
-            ExpectedError::BlockHeightError(err_msg) => println!("{}", err_msg),
-            ExpectedError::FilterError(err_msg) => {
-                println!("{}", err_msg);
-                Self::sync_event(&rocks_channel, sub_event);
-                sub_event.curr_height += 1;
-            }
-            _ => {
-                sub_event.handle_error(&rocks_channel, error.to_string());
+impl Ethereum {
+    /// logs are requested by block hash, so they belong to the polled block even if a reorg happens in between.
+    async fn fetch_logs(sub_event: &SubscribeEvent, node: &str, block: &Value) -> Result<Vec<Value>, ExpectedError> {
+        let block_object = opt_ref_to_result(block.as_object())?;
+        let mut log_filter = Map::new();
+        log_filter.insert(String::from("blockHash"), Value::String(String::from(get_str(block_object, "hash")?)));
+        for name in ["address", "topics"].iter() {
+            if let Some(value) = sub_event.options.get(*name) {
+                log_filter.insert(String::from(*name), value.clone());
             }
-        };
+        }
+        let logs = Self::call(node, "eth_getLogs", json!([ log_filter ])).await?;
+        let logs = opt_to_result(logs.as_array())?;
+        Ok(logs.clone())
     }
 
-    fn message_handler(msg: &Value, sub_events: &mut HashMap<String, SubscribeEvent>, rocks_channel: &mut Sender) {
-        let parsed_msg = msg.as_object().unwrap();
-        let method = EthereumMethod::find(get_str(parsed_msg, "method").unwrap()).unwrap();
-        let params = get_object(parsed_msg, "value").unwrap();
-        match method {
-            EthereumMethod::Subscribe => {
-                let new_event = SubscribeEvent::new(CHAIN, &params);
-                sub_events.insert(new_event.task_id.clone(), new_event.clone());
-
-                let task = SubscribeTask::from(&new_event, String::from(""));
-                let msg = RocksMsg::new(RocksMethod::Put, new_event.task_id, Value::String(json!(task).to_string()));
-                let _ = rocks_channel.send(msg);
-            }
-            EthereumMethod::Unsubscribe => {
-                let task_id = get_string(&params, "task_id").unwrap();
-                sub_events.remove(&task_id);
+    /// requests transfer logs only and decodes them. logs which share the transfer topic but are not decodable are skipped.
+    async fn fetch_token_transfers(sub_event: &SubscribeEvent, node: &str, block: &Value) -> Result<Vec<Value>, ExpectedError> {
+        let block_object = opt_ref_to_result(block.as_object())?;
+        let mut log_filter = Map::new();
+        log_filter.insert(String::from("blockHash"), Value::String(String::from(get_str(block_object, "hash")?)));
+        log_filter.insert(String::from("topics"), json!([[ token::TRANSFER_TOPIC, token::TRANSFER_SINGLE_TOPIC, token::TRANSFER_BATCH_TOPIC ]]));
+        if let Some(address) = sub_event.options.get("address") {
+            log_filter.insert(String::from("address"), address.clone());
+        }
+        let logs = Self::call(node, "eth_getLogs", json!([ log_filter ])).await?;
 
-                let msg = RocksMsg::new(RocksMethod::Delete, task_id, Value::Null);
-                let _ = rocks_channel.send(msg);
+        let mut transfers: Vec<Value> = Vec::new();
+        for log in opt_to_result(logs.as_array())?.iter() {
+            match token::decode_transfer(opt_ref_to_result(log.as_object())?) {
+                Ok(records) => transfers.extend(records),
+                Err(err) => println!("{}", err.to_string()),
             }
-            EthereumMethod::Resubscribe => {
-                let task_id = get_str(&params, "task_id").unwrap();
-                let mut sub_event = sub_events.get(task_id).unwrap().clone();
-                sub_event.node_idx = 0;
-                sub_event.status = SubscribeStatus::Working;
-                sub_events.insert(sub_event.task_id.clone(), sub_event.clone());
-
-                let task = SubscribeTask::from(&sub_event, String::from(""));
-                let msg = RocksMsg::new(RocksMethod::Put, sub_event.task_id, Value::String(json!(task).to_string()));
-                let _ = rocks_channel.send(msg);
+        }
+        Ok(transfers)
+    }
+
+    /// uses `eth_getBlockReceipts` and falls back on `eth_getTransactionReceipt` per tx if the node does not support it.
+    async fn fetch_receipts(node: &str, block: &Map<String, Value>, transactions: &Vec<Value>) -> Result<Vec<Value>, ExpectedError> {
+        let block_number = get_str(block, "number")?;
+        if let Ok(receipts) = Self::call(node, "eth_getBlockReceipts", json!([ block_number ])).await {
+            if let Some(receipts) = receipts.as_array() {
+                return Ok(receipts.clone());
             }
-            EthereumMethod::Stop => {
-                let task_id = get_str(&params, "task_id").unwrap();
-                let mut sub_event = sub_events.get(task_id).unwrap().clone();
-                sub_event.status = SubscribeStatus::Stopped;
-                sub_events.insert(sub_event.task_id.clone(), sub_event.clone());
-
-                let task = SubscribeTask::from(&sub_event, String::from(""));
-                let msg = RocksMsg::new(RocksMethod::Put, sub_event.task_id, Value::String(json!(task).to_string()));
-                let _ = rocks_channel.send(msg);
+        }
+
+        let mut receipts: Vec<Value> = Vec::new();
+        for tx in transactions.iter() {
+            let tx_hash = get_str(opt_ref_to_result(tx.as_object())?, "hash")?;
+            let receipt = Self::call(node, "eth_getTransactionReceipt", json!([ tx_hash ])).await?;
+            receipts.push(receipt);
+        }
+        Ok(receipts)
+    }
+
+    /// receipt values(ex. status, gasUsed, effectiveGasPrice, contractAddress, logs) are added to the matched tx.
+    fn merge_receipts(transactions: &Vec<Value>, receipts: &Vec<Value>) -> Result<Vec<Value>, ExpectedError> {
+        let mut receipt_map: Map<String, Value> = Map::new();
+        for receipt in receipts.iter() {
+            let receipt_object = opt_ref_to_result(receipt.as_object())?;
+            receipt_map.insert(String::from(get_str(receipt_object, "transactionHash")?), receipt.clone());
+        }
+
+        let mut merged_txs: Vec<Value> = Vec::new();
+        for tx in transactions.iter() {
+            let mut tx_object = opt_ref_to_result(tx.as_object())?.clone();
+            let tx_hash = get_str(&tx_object, "hash")?;
+            let receipt = match receipt_map.get(tx_hash) {
+                Some(receipt) => opt_ref_to_result(receipt.as_object())?,
+                None => return Err(ExpectedError::NoneError(format!("receipt does not exist! hash={}", tx_hash))),
+            };
+            for (key, value) in receipt.iter() {
+                if !tx_object.contains_key(key) {
+                    tx_object.insert(key.clone(), value.clone());
+                }
             }
-        };
+            merged_txs.push(Value::Object(tx_object));
+        }
+        Ok(merged_txs)
     }
 
-    fn poll_block(sub_event: &mut SubscribeEvent) -> Result<Value, ExpectedError> {
-        let node_index = usize::from(sub_event.node_idx);
-        let req_url = sub_event.nodes[node_index].clone();
-        let hex_height = format!("0x{:X}", sub_event.curr_height);
+    async fn call(node: &str, method: &str, params: Value) -> Result<Value, ExpectedError> {
         let req_body = json!({
             "jsonrpc": "2.0",
-            "method": "eth_getBlockByNumber",
-            "params": [ hex_height, true ],
+            "method": method,
+            "params": params,
             "id": 1
         });
-        let body = request::post(req_url.as_str(), req_body.to_string().as_str())?;
-        let block = opt_to_result(body.get("result"))?;
-        if block.is_null() {
-            return Err(ExpectedError::BlockHeightError(String::from("block has not yet been created!")));
-        }
-        let block_object = opt_ref_to_result(block.as_object())?;
-        let filter_result = libs::serde::filter(block_object, sub_event.filter.clone())?;
-        if !filter_result {
-            return Err(ExpectedError::FilterError(String::from("value does not match on filter condition!")));
+        let body = request::post(node, req_body.to_string().as_str()).await?;
+        if let Some(error) = body.get("error") {
+            let message = match error.as_object() {
+                Some(error_object) => get_str(error_object, "message").unwrap_or("unknown error!").to_string(),
+                None => error.to_string(),
+            };
+            return Err(ExpectedError::RequestError(message));
         }
-        Ok(block.clone())
+        let result = opt_to_result(body.get("result"))?;
+        Ok(result.clone())
     }
+}
 
-    fn poll_txs(sub_event: &mut SubscribeEvent) -> Result<Vec<Value>, ExpectedError> {
-        let block_value = Self::poll_block(sub_event)?;
-        let block = opt_to_result(block_value.as_object())?;
-        let transactions = get_array(block, "transactions")?;
-        Ok(transactions.clone())
-    }
+#[cfg(test)]
+mod ethereum_test {
+    use serde_json::json;
 
-    fn init_mysql(&mut self) {
-        if let Some(state) = app::plugin_state::<MySqlPlugin>() {
-            if state != State::Initialized {
-                return;
-            }
-        }
-        let json_str = fs::read_to_string("schema/eth_mysql.json").unwrap();
-        let json_schema: Value = serde_json::from_str(json_str.as_str()).unwrap();
-        let schema_map = json_schema.as_object().unwrap();
-
-        let schema = self.schema.as_mut().unwrap();
-        for (table, values) in schema_map {
-            let created_schema = Schema::from(table.clone(), values).unwrap();
-            schema.insert(table.clone(), created_schema);
-        }
+    use crate::plugin::chain::ChainAdapter;
+    use crate::plugin::ethereum::Ethereum;
 
-        let plugin_handle = app::get_plugin::<MySqlPlugin>();
-        let mut plugin = plugin_handle.lock().unwrap();
-        let mysql = plugin.downcast_mut::<MySqlPlugin>().unwrap();
+    #[test]
+    fn head_notification_test() {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "eth_subscription",
+            "params": {"subscription": "0x9ce59a13059e417087c02d3236a0b1cc", "result": {"number": "0x1b4", "hash": "0x01"}}
+        });
+        assert_eq!(Ethereum::head_notification(&notification), Some(436));
+        assert_eq!(Ethereum::head_notification(&json!({"jsonrpc": "2.0", "id": 1, "result": "0x9ce59a13059e417087c02d3236a0b1cc"})), None);
+    }
 
-        for (_, selected_schema) in schema.iter() {
-            let result = mysql.execute(selected_schema.create_table.clone(), mysql::Params::Empty);
-            if let Err(err) = result {
-                println!("error={}", err.to_string());
-            }
-        }
+    #[test]
+    fn merge_receipts_test() {
+        let transactions = vec![json!({"hash": "0x01", "from": "0xaa", "gas": "0x5208"})];
+        let receipts = vec![json!({"transactionHash": "0x01", "from": "0xaa", "status": "0x0", "gasUsed": "0x5208", "contractAddress": null})];
+        let merged = Ethereum::merge_receipts(&transactions, &receipts).unwrap();
 
-        let plugin_handle = app::get_plugin::<JsonRpcPlugin>();
-        let mut plugin = plugin_handle.lock().unwrap();
-        let jsonrpc = plugin.downcast_mut::<JsonRpcPlugin>().unwrap();
-
-        let pool = mysql.get_pool();
-        jsonrpc.add_method(String::from("eth_mysql_get_blocks"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = get_blocks::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
-            }
-            let order = get_str(&params, "order").unwrap();
-            let query = format!("select * from eth_block where number >= :from_height and number <= :to_height order by 1 {}", order);
-            let selected_params = select_value(&params, vec!["from_height", "to_height"]).unwrap();
-            let converted_params = selected_params.iter().map(|(k, v)| { (k.clone(), Value::String(format!("0x{:x}", v.as_u64().unwrap()))) }).collect();
+        assert_eq!(merged[0], json!({"hash": "0x01", "from": "0xaa", "gas": "0x5208", "transactionHash": "0x01", "status": "0x0", "gasUsed": "0x5208", "contractAddress": null}));
+    }
 
-            let result = MySqlPlugin::query_static(&pool, query, get_params(&converted_params)).unwrap();
-            Box::new(futures::future::ok(Value::Array(result)))
-        });
+    #[test]
+    fn merge_receipts_missing_test() {
+        let transactions = vec![json!({"hash": "0x01"})];
+        let receipts = vec![json!({"transactionHash": "0x02", "status": "0x1"})];
+        let merged = Ethereum::merge_receipts(&transactions, &receipts);
 
-        let pool = mysql.get_pool();
-        jsonrpc.add_method(String::from("eth_mysql_get_txs"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = get_txs::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
-            }
-            let (query, selected_params) = if params.get("txhash").is_some() {
-                let query = String::from("select * from eth_tx where hash=:txhash");
-                let selected_params = select_value(&params, vec!["txhash"]).unwrap();
-                (query, selected_params)
-            } else {
-                let order = get_str(&params, "order").unwrap();
-                let query = format!("select * from eth_tx where blockNumber >= :from_height and blockNumber <= :to_height order by 1 {}", order);
-                let selected_params = select_value(&params, vec!["from_height", "to_height"]).unwrap();
-                let converted_params = selected_params.iter().map(|(k, v)| { (k.clone(), Value::String(format!("0x{:x}", v.as_u64().unwrap()))) }).collect();
-
-                (query, converted_params)
-            };
-            let result = MySqlPlugin::query_static(&pool, query, get_params(&selected_params)).unwrap();
-            Box::new(futures::future::ok(Value::Array(result)))
-        });
+        assert!(merged.is_err());
     }
 }
-
@@ -1,29 +1,51 @@
+use std::future::Future;
+
 use appbase::*;
 use futures::executor;
-use mongodb::{Client, Database};
+use jsonrpc_core::Params as RpcParams;
+use mongodb::{Client, Collection, Database};
 use mongodb::bson::*;
-use mongodb::options::ClientOptions;
+use mongodb::error::Error;
+use mongodb::options::{ClientOptions, ReplaceOptions};
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
+use tokio::time;
 
-use crate::{libs, message};
-use crate::libs::serde::{get_object, get_str};
+use crate::{enumeration, libs, message};
+use crate::libs::delivery;
+use crate::libs::rocks::get_by_prefix_static;
+use crate::libs::serde::{get_array, get_bool, get_object, get_str, get_u64};
+use crate::plugin::jsonrpc::JsonRpcPlugin;
+use crate::plugin::rocks::RocksPlugin;
+use crate::types::channel::{next_message, TaskQueues};
+use crate::types::enumeration::Enumeration;
+use crate::validation::get_task;
 
+/*
+ * documents are written by a worker of each task, so a task retrying with backoff does not hold the others.
+ * every error but a duplicate key is retried, and documents still failing are kept in the dead letter store of RocksDB.
+ */
 pub struct MongoPlugin {
     db: Option<Database>,
+    retries: u32,
     monitor: Option<channel::Receiver>,
 }
 
-plugin::requires!(MongoPlugin; );
+plugin::requires!(MongoPlugin; JsonRpcPlugin, RocksPlugin);
+
+message!((MongoMsg; {task_id: String}, {collection: String}, {document: Value}); (MongoMethod; {Flush: "flush"}, {Delete: "delete"}));
 
-message!(MongoMsg; {collection: String}, {document: Value});
+/// duplicate key error of MongoDB.
+const DUPLICATE_KEY: &str = "E11000";
 
 impl Plugin for MongoPlugin {
     fn new() -> Self {
         app::arg(clap::Arg::new("mongo::url").long("mongo-url").takes_value(true));
+        app::arg(clap::Arg::new("mongo::retries").long("mongo-retries").takes_value(true));
 
         MongoPlugin {
             db: None,
+            retries: delivery::DEFAULT_RETRIES,
             monitor: None,
         }
     }
@@ -34,37 +56,133 @@ impl Plugin for MongoPlugin {
         client_opts.app_name = Some(String::from("MongoDB"));
         let client = Client::with_options(client_opts).unwrap();
         self.db = Some(client.database("ufc"));
+        match libs::opts::u64("mongo::retries") {
+            Ok(Some(retries)) => self.retries = retries as u32,
+            Ok(None) => {}
+            Err(err) => panic!("{}", err.to_string()),
+        }
         self.monitor = Some(app::subscribe_channel(String::from("mongo")));
+        Self::register_jsonrpc();
     }
 
     fn startup(&mut self) {
         let db = self.db.as_ref().unwrap().clone();
         let monitor = self.monitor.take().unwrap();
+        let rocks_channel = app::get_channel(String::from("rocks"));
         let app = app::quit_handle().unwrap();
-        Self::recv(db, monitor, app);
+        Self::recv(db, monitor, rocks_channel, self.retries, app);
     }
 
     fn shutdown(&mut self) {}
 }
 
 impl MongoPlugin {
-    fn recv(db: Database, mut monitor: channel::Receiver, app: QuitHandle) {
+    fn register_jsonrpc() {
+        let plugin_handle = app::get_plugin::<JsonRpcPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let jsonrpc = plugin.downcast_mut::<JsonRpcPlugin>().unwrap();
+
+        let plugin_handle = app::get_plugin::<RocksPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let rocks = plugin.downcast_mut::<RocksPlugin>().unwrap();
+
+        let rocks_db = rocks.get_db();
+        jsonrpc.add_method(String::from("mongo_get_dead_letters"), move |params: RpcParams| {
+            let params: Map<String, Value> = params.parse().unwrap();
+            let verified = get_task::verify(&params);
+            if verified.is_err() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+
+            let prefix = delivery::dead_letter_prefix("mongo", params.get("task_id").and_then(|task_id| { task_id.as_str() }));
+            Box::new(futures::future::ok(get_by_prefix_static(&rocks_db, prefix.as_str())))
+        });
+    }
+
+    fn recv(db: Database, mut monitor: channel::Receiver, rocks_channel: channel::Sender, retries: u32, app: QuitHandle) {
         app::spawn(async move {
-            if let Ok(msg) = monitor.try_recv() {
-                let parsed_msg = msg.as_object().unwrap();
-                let collection_name = get_str(parsed_msg, "collection").unwrap();
-                let value = get_object(parsed_msg, "document").unwrap();
-
-                let collection = db.collection::<Document>(collection_name);
-                let document = libs::mongo::get_doc(value);
-                let result = collection.insert_one(document.clone(), None).await;
+            let mut queues = TaskQueues::new(move |msg: Value| {
+                let db = db.clone();
+                let rocks_channel = rocks_channel.clone();
+                async move { Self::handle(&db, &msg, retries, &rocks_channel).await }
+            });
+            while let Some(msg) = next_message(&mut monitor).await {
+                let task_id = String::from(get_str(msg.as_object().unwrap(), "task_id").unwrap_or(""));
+                queues.send(task_id.as_str(), msg);
+                if app.is_quiting() {
+                    break;
+                }
+            }
+        });
+    }
+
+    async fn handle(db: &Database, msg: &Value, retries: u32, rocks_channel: &channel::Sender) {
+        let parsed_msg = msg.as_object().unwrap();
+        let method = MongoMethod::find(get_str(parsed_msg, "method").unwrap()).unwrap();
+        let collection_name = get_str(parsed_msg, "collection").unwrap();
+        let value = get_object(parsed_msg, "document").unwrap();
+
+        let collection = db.collection::<Document>(collection_name);
+        match method {
+            MongoMethod::Flush => Self::flush(&collection, value, retries, rocks_channel).await,
+            MongoMethod::Delete => {
+                let result = Self::retry(retries, || { collection.delete_many(libs::mongo::get_doc(value), None) }).await;
                 if let Err(err) = result {
                     println!("mongo_error={:?}", err);
                 }
             }
-            if !app.is_quiting() {
-                Self::recv(db, monitor, app);
+        }
+    }
+
+    /// documents of a block. a document of an upsert table carries its natural key as `_id` and replaces the previous one.
+    /// a duplicate key of an insert sent again is already written. documents failing after the retries go to the dead letter store,
+    /// and the block is acknowledged once every document is handled.
+    async fn flush(collection: &Collection<Document>, block: &Map<String, Value>, retries: u32, rocks_channel: &channel::Sender) {
+        let task_id = get_str(block, "task_id").unwrap();
+        let height = get_u64(block, "height").unwrap();
+        let upsert = get_bool(block, "upsert").unwrap_or(false);
+        for (idx, value) in get_array(block, "documents").unwrap().iter().enumerate() {
+            let result = Self::retry(retries, || {
+                let document = libs::mongo::get_doc(value.as_object().unwrap());
+                async move {
+                    if upsert {
+                        let filter = doc! { "_id": document.get("_id").cloned().unwrap_or(Bson::Null) };
+                        let options = ReplaceOptions::builder().upsert(true).build();
+                        collection.replace_one(filter, document, options).await.map(|_| {})
+                    } else {
+                        collection.insert_one(document, None).await.map(|_| {})
+                    }
+                }
+            }).await;
+            match result {
+                Err(err) if !Self::is_duplicate_key(&err) => {
+                    let letter = json!({"collection": collection.name(), "document": value});
+                    delivery::dead_letter(rocks_channel, "mongo", task_id, height, idx, letter.as_object().unwrap().clone(), err.to_string().as_str());
+                }
+                _ => {}
             }
-        });
+        }
+        delivery::ack(task_id, height, "mongo");
+    }
+
+    /// retries every error but a duplicate key with backoff, as the driver does not tell a rejected document from a lost server for sure.
+    async fn retry<T, F, Fut>(retries: u32, f: F) -> Result<T, Error> where F: Fn() -> Fut, Fut: Future<Output = Result<T, Error>> {
+        let mut attempt: u32 = 0;
+        loop {
+            match f().await {
+                Err(err) if !Self::is_duplicate_key(&err) && attempt < retries => {
+                    println!("mongo error! attempt={}, error={}", attempt + 1, err);
+                    time::sleep(delivery::retry_delay(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn is_duplicate_key(err: &Error) -> bool {
+        err.to_string().contains(DUPLICATE_KEY)
     }
 }
@@ -1,30 +1,79 @@
+use std::thread;
+
 use appbase::*;
+use jsonrpc_core::Params as RpcParams;
 use mysql::*;
 use mysql::prelude::Queryable;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value, Number};
 
-use crate::libs::mysql::get_params;
+use crate::{enumeration, message};
+use crate::libs::delivery;
+use crate::libs::mysql::{get_params, get_values, multi_row_query};
 use crate::libs::opts;
+use crate::libs::rocks::get_by_prefix_static;
 use crate::libs::serde::{get_object, get_str};
-use crate::message;
 use crate::plugin::jsonrpc::JsonRpcPlugin;
+use crate::plugin::rocks::{RocksMethod, RocksMsg, RocksPlugin};
+use crate::types::channel::{next_message, TaskQueues};
+use crate::types::enumeration::Enumeration;
+use crate::types::mysql::Schema;
+use crate::types::subscribe::timestamp;
+use crate::validation::get_task;
+
+/*
+ * rows of a block are written by multi-row inserts in a transaction, together with the height checkpoint of the task.
+ * each task has its own worker, and transient errors are retried with backoff on it.
+ * rows failing for good are kept in the dead letter store of RocksDB.
+ */
+/// height of the last block written for each task.
+pub const CHECKPOINT_TABLE: &str = "ufc_checkpoint";
+/// schema versions applied to each table.
+const MIGRATION_TABLE: &str = "ufc_schema_migrations";
+const MAX_ROWS_PER_INSERT: usize = 500;
+/// lock wait timeout, deadlock, too many connections, server shutdown, server gone away and lost connection.
+const TRANSIENT_CODES: [u16; 6] = [1205, 1213, 1040, 1053, 2006, 2013];
 
 pub struct MySqlPlugin {
     pool: Option<Pool>,
+    retries: u32,
+    /// true if migrations are printed rather than applied.
+    dry_run: bool,
     monitor: Option<channel::Receiver>,
 }
 
-message!(MySqlMsg; {query: String}, {value: Value});
+message!((MySqlMsg; {value: Value}); (MySqlMethod; {Execute: "execute"}, {Flush: "flush"}));
+
+/// rows of a block and the height checkpoint of its task.
+#[derive(Deserialize)]
+struct Block {
+    task_id: String,
+    height: u64,
+    inserts: Vec<Insert>,
+}
+
+/// rows of a table with its single row insert query.
+#[derive(Deserialize)]
+struct Insert {
+    query: String,
+    rows: Vec<Map<String, Value>>,
+}
+
+/// (insert query, row, error) of a row failed on its own.
+type FailedRow = (String, Map<String, Value>, String);
 
-plugin::requires!(MySqlPlugin; JsonRpcPlugin);
+plugin::requires!(MySqlPlugin; JsonRpcPlugin, RocksPlugin);
 
 impl Plugin for MySqlPlugin {
     fn new() -> Self {
         app::arg(clap::Arg::new("mysql::url").long("mysql-url").takes_value(true));
+        app::arg(clap::Arg::new("mysql::retries").long("mysql-retries").takes_value(true));
+        app::arg(clap::Arg::new("mysql::migration-dry-run").long("mysql-migration-dry-run"));
 
         MySqlPlugin {
             monitor: None,
+            retries: delivery::DEFAULT_RETRIES,
+            dry_run: false,
             pool: None,
         }
     }
@@ -33,47 +82,226 @@ impl Plugin for MySqlPlugin {
         let mysql_url = opts::string("mysql::url").unwrap();
         let opts = Opts::from_url(mysql_url.as_str()).unwrap();
         let pool = Pool::new(opts).unwrap();
+        let create_checkpoint = format!("create table if not exists `{}` (`task_id` varchar(255) not null, `height` bigint(20) unsigned not null, PRIMARY KEY (`task_id`)) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4", CHECKPOINT_TABLE);
+        pool.get_conn().unwrap().query_drop(create_checkpoint).unwrap();
+        let create_migration = format!("create table if not exists `{}` (`table_name` varchar(255) not null, `version` char(64) not null, `statements` text not null, `applied_at` bigint(20) unsigned not null, PRIMARY KEY (`table_name`, `version`)) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4", MIGRATION_TABLE);
+        pool.get_conn().unwrap().query_drop(create_migration).unwrap();
         self.pool = Some(pool);
+        match opts::u64("mysql::retries") {
+            Ok(Some(retries)) => self.retries = retries as u32,
+            Ok(None) => {}
+            Err(err) => panic!("{}", err.to_string()),
+        }
+        self.dry_run = opts::bool("mysql::migration-dry-run").unwrap();
         self.monitor = Some(app::subscribe_channel(String::from("mysql")));
+        Self::register_jsonrpc();
     }
 
     fn startup(&mut self) {
         let pool = self.pool.as_ref().unwrap().clone();
         let monitor = self.monitor.take().unwrap();
+        let rocks_channel = app::get_channel(String::from("rocks"));
         let app = app::quit_handle().unwrap();
-        Self::recv(pool, monitor, app);
+        Self::recv(pool, monitor, rocks_channel, self.retries, app);
     }
 
     fn shutdown(&mut self) {}
 }
 
 impl MySqlPlugin {
-    fn recv(pool: Pool, mut monitor: channel::Receiver, app: QuitHandle) {
+    fn register_jsonrpc() {
+        let plugin_handle = app::get_plugin::<JsonRpcPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let jsonrpc = plugin.downcast_mut::<JsonRpcPlugin>().unwrap();
+
+        let plugin_handle = app::get_plugin::<RocksPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let rocks = plugin.downcast_mut::<RocksPlugin>().unwrap();
+
+        let rocks_db = rocks.get_db();
+        jsonrpc.add_method(String::from("mysql_get_dead_letters"), move |params: RpcParams| {
+            let params: Map<String, Value> = params.parse().unwrap();
+            let verified = get_task::verify(&params);
+            if verified.is_err() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+
+            let prefix = delivery::dead_letter_prefix("mysql", params.get("task_id").and_then(|task_id| { task_id.as_str() }));
+            Box::new(futures::future::ok(get_by_prefix_static(&rocks_db, prefix.as_str())))
+        });
+    }
+
+    /// messages of a task are handled in order by its own worker on a blocking thread, so the writes of a task keep the height order
+    /// and a task waiting for a retry does not hold the writes of the others.
+    fn recv(pool: Pool, mut monitor: channel::Receiver, rocks_channel: channel::Sender, retries: u32, app: QuitHandle) {
         app::spawn(async move {
-            if let Ok(msg) = monitor.try_recv() {
-                let parsed_msg = msg.as_object().unwrap();
-                let query = get_str(parsed_msg, "query").unwrap();
-                let value = get_object(parsed_msg, "value").unwrap();
-                let params = get_params(value);
-                let result = pool.get_conn().unwrap().exec_drop(query, params);
+            let mut queues = TaskQueues::new(move |msg: Value| {
+                let pool = pool.clone();
+                let rocks_channel = rocks_channel.clone();
+                async move {
+                    let result = tokio::task::spawn_blocking(move || { Self::handle(&pool, &msg, retries, &rocks_channel) }).await;
+                    if let Err(err) = result {
+                        println!("mysql_error={:?}", err);
+                    }
+                }
+            });
+            while let Some(msg) = next_message(&mut monitor).await {
+                let task_id = String::from(msg.get("value").and_then(|value| { value.get("task_id") }).and_then(|task_id| { task_id.as_str() }).unwrap_or(""));
+                queues.send(task_id.as_str(), msg);
+                if app.is_quiting() {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn handle(pool: &Pool, msg: &Value, retries: u32, rocks_channel: &channel::Sender) {
+        let parsed_msg = msg.as_object().unwrap();
+        let method = MySqlMethod::find(get_str(parsed_msg, "method").unwrap()).unwrap();
+        let value = get_object(parsed_msg, "value").unwrap();
+        match method {
+            MySqlMethod::Execute => {
+                let query = get_str(value, "query").unwrap();
+                let params = get_params(get_object(value, "params").unwrap());
+                let result = Self::retry(retries, || { pool.get_conn()?.exec_drop(query, params.clone()) });
                 if let Err(err) = result {
                     println!("mysql_error={:?}", err);
                 }
             }
-            if !app.is_quiting() {
-                Self::recv(pool, monitor, app);
+            MySqlMethod::Flush => {
+                let block: Block = serde_json::from_value(Value::Object(value.clone())).unwrap();
+                Self::flush(pool, &block, retries, rocks_channel);
             }
-        });
+        }
     }
 
-    pub fn get_pool(&self) -> Pool {
-        self.pool.as_ref().unwrap().clone()
+    /// a block failing for good is written again row by row, so only the failing rows go to the dead letter store.
+    /// every row goes there if transient errors outlast the retries. the block is acknowledged either way, and is not sent again.
+    fn flush(pool: &Pool, block: &Block, retries: u32, rocks_channel: &channel::Sender) {
+        let result = match Self::retry(retries, || { Self::write(pool, block, false) }) {
+            Err(err) if !Self::is_transient(&err) => {
+                println!("mysql batch failed, writing row by row! task_id={}, height={}, error={}", block.task_id, block.height, err);
+                Self::retry(retries, || { Self::write(pool, block, true) })
+            }
+            result => result,
+        };
+        let failed_rows: Vec<FailedRow> = match result {
+            Ok(failed_rows) => failed_rows,
+            Err(err) => {
+                let error = err.to_string();
+                let error = error.as_str();
+                block.inserts.iter().flat_map(|insert| {
+                    insert.rows.iter().map(move |row| { (insert.query.clone(), row.clone(), String::from(error)) })
+                }).collect()
+            }
+        };
+        for (idx, (query, row, error)) in failed_rows.into_iter().enumerate() {
+            let letter = json!({"query": query, "row": row});
+            delivery::dead_letter(rocks_channel, "mysql", block.task_id.as_str(), block.height, idx, letter.as_object().unwrap().clone(), error.as_str());
+        }
+        delivery::ack(block.task_id.as_str(), block.height, "mysql");
     }
 
-    pub fn execute(&self, query: String, params: Params) -> Result<()> {
-        let pool = self.pool.as_ref().unwrap();
-        let _ = pool.get_conn().unwrap().exec_drop(query, params)?;
-        Ok(())
+    /// writes the rows and the checkpoint in a transaction. a block at or below the checkpoint is already written, and skipped.
+    /// rows failed on their own are returned only when writing row by row.
+    fn write(pool: &Pool, block: &Block, row_by_row: bool) -> Result<Vec<FailedRow>> {
+        let mut failed_rows: Vec<FailedRow> = Vec::new();
+        let mut tx = pool.start_transaction(TxOpts::default())?;
+        let checkpoint: Option<u64> = tx.exec_first(format!("select height from {} where task_id=? for update", CHECKPOINT_TABLE), (block.task_id.as_str(),))?;
+        if checkpoint.map_or(false, |checkpoint| { checkpoint >= block.height }) {
+            return Ok(failed_rows);
+        }
+        for insert in block.inserts.iter() {
+            if row_by_row {
+                for row in insert.rows.iter() {
+                    match tx.exec_drop(insert.query.as_str(), get_params(row)) {
+                        Err(err) if Self::is_transient(&err) => return Err(err),
+                        Err(err) => failed_rows.push((insert.query.clone(), row.clone(), err.to_string())),
+                        Ok(_) => {}
+                    }
+                }
+            } else {
+                for rows in insert.rows.chunks(MAX_ROWS_PER_INSERT) {
+                    let params: Vec<mysql::Value> = rows.iter().flat_map(get_values).collect();
+                    tx.exec_drop(multi_row_query(insert.query.as_str(), rows.len()), params)?;
+                }
+            }
+        }
+        let upsert = format!("insert into {} (task_id, height) values (?, ?) on duplicate key update height=values(height)", CHECKPOINT_TABLE);
+        tx.exec_drop(upsert, (block.task_id.as_str(), block.height))?;
+        tx.commit()?;
+        Ok(failed_rows)
+    }
+
+    /// retries transient errors with backoff, which holds only the worker of the task.
+    fn retry<T, F>(retries: u32, mut f: F) -> Result<T> where F: FnMut() -> Result<T> {
+        let mut attempt: u32 = 0;
+        loop {
+            match f() {
+                Err(err) if Self::is_transient(&err) && attempt < retries => {
+                    println!("mysql transient error! attempt={}, error={}", attempt + 1, err);
+                    thread::sleep(delivery::retry_delay(attempt));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn is_transient(err: &Error) -> bool {
+        match err {
+            Error::IoError(_) | Error::DriverError(_) => true,
+            Error::MySqlError(err) => TRANSIENT_CODES.contains(&err.code),
+            _ => false,
+        }
+    }
+
+    /// creates the table, or adds what the existing table misses from the schema, once for each schema version.
+    /// ddl is printed instead in the dry run. a changed type is only warned, as altering it may lose the existing values.
+    pub fn migrate(&self, schema: &Schema) -> Result<()> {
+        let mut conn = self.pool.as_ref().unwrap().get_conn()?;
+        let applied: Option<String> = conn.exec_first(format!("select version from {} where table_name=? and version=?", MIGRATION_TABLE), (schema.table.as_str(), schema.version.as_str()))?;
+        if applied.is_some() {
+            return Ok(());
+        }
+        let column_types: Vec<(String, String)> = conn.exec("select column_name, column_type from information_schema.columns where table_schema=database() and table_name=?", (schema.table.as_str(),))?;
+        let statements = if column_types.is_empty() {
+            vec![schema.create_table.clone()]
+        } else {
+            for (column, existing, changed) in schema.changed_types(&column_types) {
+                println!("mysql column type is changed, but not altered! table={}, column={}, type={}, schema_type={}", schema.table, column, existing, changed);
+            }
+            let columns: Vec<String> = column_types.into_iter().map(|(column, _)| { column }).collect();
+            let key_names: Vec<String> = conn.exec("select distinct index_name from information_schema.statistics where table_schema=database() and table_name=?", (schema.table.as_str(),))?;
+            schema.migration(&columns, &key_names)
+        };
+        if self.dry_run {
+            for statement in statements.iter() {
+                println!("planned migration! table={}, version={}, statement={}", schema.table, schema.version, statement);
+            }
+            return Ok(());
+        }
+        for statement in statements.iter() {
+            println!("applying migration! table={}, statement={}", schema.table, statement);
+            conn.query_drop(statement)?;
+        }
+        let record = format!("insert into {} (table_name, version, statements, applied_at) values (?, ?, ?, ?)", MIGRATION_TABLE);
+        conn.exec_drop(record, (schema.table.as_str(), schema.version.as_str(), statements.join(";\n"), timestamp()))
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// height checkpoints by task id.
+    pub fn checkpoints_static(pool: &Pool) -> Result<Vec<(String, u64)>> {
+        pool.get_conn()?.query(format!("select task_id, height from {}", CHECKPOINT_TABLE))
+    }
+
+    pub fn get_pool(&self) -> Pool {
+        self.pool.as_ref().unwrap().clone()
     }
 
     pub fn query_static(pool: &Pool, query: String, params: Params) -> Result<Vec<Value>> {
@@ -0,0 +1,206 @@
+use std::fs;
+use std::time::Duration;
+
+use appbase::*;
+use jsonrpc_core::Params;
+use serde_json::{json, Map, Value};
+
+use crate::libs;
+use crate::libs::request::RequestConfig;
+use crate::libs::rocks::{get_by_prefix_static, get_static};
+use crate::libs::serde::{get_str, get_string_vec};
+use crate::plugin::jsonrpc::JsonRpcPlugin;
+use crate::plugin::rocks::{RocksMethod, RocksMsg, RocksPlugin};
+use crate::types::node::Endpoint;
+use crate::validation::{node_add, node_remove};
+
+/*
+ * `NodePlugin` fills the node registry from the file of `node::registry` and RocksDB, and manages it with `node_*` JSON-RPC methods.
+ * nodes added by `node_add` are kept in RocksDB and replace the ones of the same name in the file.
+ * their credentials are kept apart under `node_secret:` keys, which no JSON-RPC method returns, and `node:` keys keep them redacted.
+ * it also configures the shared HTTP client of `libs::request` before any chain plugin sends a request.
+ */
+pub struct NodePlugin;
+
+plugin::requires!(NodePlugin; JsonRpcPlugin, RocksPlugin);
+
+impl Plugin for NodePlugin {
+    fn new() -> Self {
+        app::arg(clap::Arg::new("node::registry").long("node-registry").takes_value(true));
+        app::arg(clap::Arg::new("node::timeout").long("node-timeout").takes_value(true));
+        app::arg(clap::Arg::new("node::connect-timeout").long("node-connect-timeout").takes_value(true));
+        app::arg(clap::Arg::new("node::retries").long("node-retries").takes_value(true));
+        app::arg(clap::Arg::new("node::rate-limit").long("node-rate-limit").takes_value(true));
+        app::arg(clap::Arg::new("node::host-rate-limit").long("node-host-rate-limit").takes_value(true));
+
+        NodePlugin
+    }
+
+    fn initialize(&mut self) {
+        Self::configure_request();
+        if let Ok(registry) = libs::opts::string("node::registry") {
+            Self::load_registry(registry.as_str());
+        }
+        self.load_nodes();
+        self.register_jsonrpc();
+    }
+
+    fn startup(&mut self) {}
+
+    fn shutdown(&mut self) {}
+}
+
+impl NodePlugin {
+    fn key(name: &str) -> String {
+        format!("node:{}", name)
+    }
+
+    fn secret_key(name: &str) -> String {
+        format!("{}{}", libs::node::SECRET_PREFIX, name)
+    }
+
+    /// timeouts are in milliseconds.
+    fn configure_request() {
+        let mut config = RequestConfig::default();
+        if let Some(timeout) = Self::u64_arg("node::timeout") {
+            config.timeout = Duration::from_millis(timeout);
+        }
+        if let Some(connect_timeout) = Self::u64_arg("node::connect-timeout") {
+            config.connect_timeout = Duration::from_millis(connect_timeout);
+        }
+        if let Some(retries) = Self::u64_arg("node::retries") {
+            config.retries = retries as u32;
+        }
+        if let Some(rate_limit) = Self::u64_arg("node::rate-limit") {
+            config.rate_limit = rate_limit;
+        }
+        if let Ok(host_rate_limit) = libs::opts::string("node::host-rate-limit") {
+            match libs::request::parse_rate_limits(host_rate_limit.as_str()) {
+                Ok(host_rate_limits) => config.host_rate_limits = host_rate_limits,
+                Err(err) => panic!("node::host-rate-limit is invalid! error={}", err.to_string()),
+            }
+        }
+        libs::request::configure(config);
+    }
+
+    fn u64_arg(key: &str) -> Option<u64> {
+        let value = libs::opts::string(key).ok()?;
+        match value.parse::<u64>() {
+            Ok(value) => Some(value),
+            Err(_) => panic!("{} is not u64! value={}", key, value),
+        }
+    }
+
+    /// a JSON array of the `node_add` params.
+    fn load_registry(registry: &str) {
+        let json_str = fs::read_to_string(registry).unwrap();
+        let nodes: Vec<Map<String, Value>> = serde_json::from_str(json_str.as_str()).unwrap();
+        for node in nodes.into_iter() {
+            if let Err(err) = node_add::verify(&node) {
+                panic!("invalid node in registry! registry={}, error={}", registry, err.to_string());
+            }
+            libs::node::insert(serde_json::from_value(Value::Object(node)).unwrap());
+        }
+    }
+
+    fn load_nodes(&self) {
+        let plugin_handle = app::get_plugin::<RocksPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let rocks = plugin.downcast_mut::<RocksPlugin>().unwrap();
+
+        let rocks_db = rocks.get_db();
+        let raw_nodes = get_by_prefix_static(&rocks_db, Self::key("").as_str());
+        for raw_node in raw_nodes.as_array().unwrap().iter() {
+            let mut endpoint: Endpoint = serde_json::from_value(raw_node.clone()).unwrap();
+            if endpoint.has_credentials() {
+                // a node stored before its credentials were kept apart takes them from the file.
+                let registered = match get_static(&rocks_db, Self::secret_key(endpoint.name.as_str()).as_str()) {
+                    Value::Null => libs::node::get(endpoint.name.as_str()),
+                    secret => Some(serde_json::from_value::<Endpoint>(secret).unwrap()),
+                };
+                match registered {
+                    Some(registered) => endpoint.restore_credentials(&registered),
+                    None => panic!("credentials of node are not stored! add the node to the registry file. name={}", endpoint.name),
+                }
+            }
+            libs::node::insert(endpoint);
+        }
+    }
+
+    fn register_jsonrpc(&self) {
+        let plugin_handle = app::get_plugin::<JsonRpcPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let jsonrpc = plugin.downcast_mut::<JsonRpcPlugin>().unwrap();
+
+        let plugin_handle = app::get_plugin::<RocksPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let rocks = plugin.downcast_mut::<RocksPlugin>().unwrap();
+
+        let rocks_channel = app::get_channel(String::from("rocks"));
+        jsonrpc.add_method(String::from("node_add"), move |params: Params| {
+            let params: Map<String, Value> = params.parse().unwrap();
+            let verified = node_add::verify(&params);
+            if verified.is_err() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+
+            let endpoint: Endpoint = serde_json::from_value(Value::Object(params)).unwrap();
+            let secret_key = Self::secret_key(endpoint.name.as_str());
+            let secret_msg = if endpoint.has_credentials() {
+                RocksMsg::new(RocksMethod::Put, secret_key, Value::String(json!(endpoint).to_string()))
+            } else {
+                RocksMsg::new(RocksMethod::Delete, secret_key, Value::Null)
+            };
+            let _ = rocks_channel.send(secret_msg);
+            let msg = RocksMsg::new(RocksMethod::Put, Self::key(endpoint.name.as_str()), Value::String(endpoint.redacted().to_string()));
+            let _ = rocks_channel.send(msg);
+            let result = format!("node added! name={}", endpoint.name);
+            libs::node::insert(endpoint);
+            Box::new(futures::future::ok(Value::String(result)))
+        });
+
+        let rocks_channel = app::get_channel(String::from("rocks"));
+        let rocks_db = rocks.get_db();
+        jsonrpc.add_method(String::from("node_remove"), move |params: Params| {
+            let params: Map<String, Value> = params.parse().unwrap();
+            let verified = node_remove::verify(&params);
+            if verified.is_err() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+
+            let name = get_str(&params, "name").unwrap();
+            if libs::node::get(name).is_none() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(format!("node does not exist! name={}", name)));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+            let tasks = get_by_prefix_static(&rocks_db, "task:");
+            let task_ids: Vec<&str> = tasks.as_array().unwrap().iter().filter_map(|task| { task.as_object() }).filter(|task| {
+                get_string_vec(task, "nodes").iter().any(|node| { node == name }) || get_str(task, "websocket").map_or(false, |websocket| { websocket == name })
+            }).map(|task| { get_str(task, "task_id").unwrap_or("") }).collect();
+            if !task_ids.is_empty() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(format!("node is used by subscriptions! task_id=[{}]", task_ids.join(", "))));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+
+            let _ = rocks_channel.send(RocksMsg::new(RocksMethod::Delete, Self::key(name), Value::Null));
+            let _ = rocks_channel.send(RocksMsg::new(RocksMethod::Delete, Self::secret_key(name), Value::Null));
+            libs::node::remove(name);
+            Box::new(futures::future::ok(Value::String(format!("node removed! name={}", name))))
+        });
+
+        jsonrpc.add_method(String::from("node_list"), move |_: Params| {
+            let nodes: Vec<Value> = libs::node::list().iter().map(|endpoint| { endpoint.redacted() }).collect();
+            Box::new(futures::future::ok(Value::Array(nodes)))
+        });
+
+        jsonrpc.add_method(String::from("node_metrics"), move |_: Params| {
+            Box::new(futures::future::ok(json!(libs::request::metrics())))
+        });
+    }
+}
@@ -1,64 +1,222 @@
+use std::collections::BTreeSet;
 use std::sync::Arc;
+use std::time::Duration;
 
-use amiquip::{Connection, Exchange, Publish};
+use amiquip::{Channel, Confirm, Connection, Exchange, Publish};
 use appbase::*;
-use futures::lock::Mutex as FutureMutex;
+use jsonrpc_core::Params as RpcParams;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tokio::time;
 
-use crate::libs::opts;
+use crate::libs::{delivery, opts};
+use crate::libs::rocks::get_by_prefix_static;
+use crate::libs::serde::{get_array, get_str, get_u64};
+use crate::message;
+use crate::plugin::jsonrpc::JsonRpcPlugin;
+use crate::plugin::rocks::RocksPlugin;
+use crate::types::channel::next_message;
+use crate::validation::get_task;
 
+/*
+ * messages are published in order on a single connection, and a delivery is acknowledged once the broker confirms every message.
+ * amiquip blocks, so it runs on the blocking threads. a lost broker is reconnected with backoff until it comes back,
+ * and the delivery is not acknowledged meanwhile. messages failing to publish or to be confirmed are retried,
+ * then kept in the dead letter store of RocksDB.
+ */
 pub struct RabbitPlugin {
-    conn: Option<RabbitConnection>,
+    url: Option<String>,
+    conn: Option<Connection>,
+    retries: u32,
     monitor: Option<channel::Receiver>,
 }
 
-type RabbitConnection = Arc<FutureMutex<Connection>>;
+plugin::requires!(RabbitPlugin; JsonRpcPlugin, RocksPlugin);
 
-plugin::requires!(RabbitPlugin; );
+message!(RabbitMsg; {messages: Value}, {delivery: Value});
+
+/// how long the broker may take to confirm the published messages.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// a connection failure waits for the broker, and a publish failure counts to the retries.
+enum Failure {
+    Connection(String),
+    Publish(String),
+}
 
 impl Plugin for RabbitPlugin {
     fn new() -> Self {
         app::arg(clap::Arg::new("rabbit-mq::url").long("rabbit-mq-url").takes_value(true));
         app::arg(clap::Arg::new("rabbit-mq::queue").long("rabbit-mq-queue").takes_value(true));
+        app::arg(clap::Arg::new("rabbit-mq::retries").long("rabbit-mq-retries").takes_value(true));
 
         RabbitPlugin {
+            url: None,
             conn: None,
+            retries: delivery::DEFAULT_RETRIES,
             monitor: None,
         }
     }
 
     fn initialize(&mut self) {
         let rabbit_mq_url = opts::string("rabbit-mq::url").unwrap();
-        self.conn = Some(Arc::new(FutureMutex::new(Connection::insecure_open(rabbit_mq_url.as_str()).unwrap())));
+        self.conn = Some(Connection::insecure_open(rabbit_mq_url.as_str()).unwrap());
+        self.url = Some(rabbit_mq_url);
+        match opts::u64("rabbit-mq::retries") {
+            Ok(Some(retries)) => self.retries = retries as u32,
+            Ok(None) => {}
+            Err(err) => panic!("{}", err.to_string()),
+        }
         self.monitor = Some(app::subscribe_channel(String::from("rabbit")));
+        Self::register_jsonrpc();
     }
 
     fn startup(&mut self) {
-        let conn = self.conn.as_ref().unwrap().clone();
+        let url = self.url.as_ref().unwrap().clone();
+        let conn = self.conn.take();
         let monitor = self.monitor.take().unwrap();
+        let rocks_channel = app::get_channel(String::from("rocks"));
         let app = app::quit_handle().unwrap();
-        Self::recv(conn, monitor, app);
+        Self::recv(url, conn, monitor, rocks_channel, self.retries, app);
     }
 
     fn shutdown(&mut self) {}
 }
 
 impl RabbitPlugin {
-    fn recv(conn: RabbitConnection, mut monitor: channel::Receiver, app: QuitHandle) {
+    fn register_jsonrpc() {
+        let plugin_handle = app::get_plugin::<JsonRpcPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let jsonrpc = plugin.downcast_mut::<JsonRpcPlugin>().unwrap();
+
+        let plugin_handle = app::get_plugin::<RocksPlugin>();
+        let mut plugin = plugin_handle.lock().unwrap();
+        let rocks = plugin.downcast_mut::<RocksPlugin>().unwrap();
+
+        let rocks_db = rocks.get_db();
+        jsonrpc.add_method(String::from("rabbit_get_dead_letters"), move |params: RpcParams| {
+            let params: Map<String, Value> = params.parse().unwrap();
+            let verified = get_task::verify(&params);
+            if verified.is_err() {
+                let mut error = Map::new();
+                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
+                return Box::new(futures::future::ok(Value::Object(error)));
+            }
+
+            let prefix = delivery::dead_letter_prefix("rabbit", params.get("task_id").and_then(|task_id| { task_id.as_str() }));
+            Box::new(futures::future::ok(get_by_prefix_static(&rocks_db, prefix.as_str())))
+        });
+    }
+
+    fn recv(url: String, mut conn: Option<Connection>, mut monitor: channel::Receiver, rocks_channel: channel::Sender, retries: u32, app: QuitHandle) {
         app::spawn(async move {
-            if let Some(mut conn_lock) = conn.try_lock() {
-                let channel = conn_lock.open_channel(None).unwrap();
-                let exchange = Exchange::direct(&channel);
-                if let Ok(msg) = monitor.try_recv() {
-                    let queue = opts::string("rabbit-mq::queue").unwrap();
-                    let result = exchange.publish(Publish::new(msg.as_str().unwrap().as_bytes(), queue.as_str()));
-                    if let Err(err) = result {
-                        println!("rabbit_error={:?}", err);
+            let queue = opts::string("rabbit-mq::queue").unwrap();
+            while let Some(msg) = next_message(&mut monitor).await {
+                let parsed_msg = msg.as_object().unwrap();
+                let messages: Arc<Vec<String>> = Arc::new(get_array(parsed_msg, "messages").unwrap().iter().map(|message| { String::from(message.as_str().unwrap()) }).collect());
+                let delivery_value = parsed_msg.get("delivery").cloned().unwrap_or(Value::Null);
+                let mut published: usize = 0;
+                let mut attempt: u32 = 0;
+                let mut reconnects: u32 = 0;
+                loop {
+                    if published == messages.len() {
+                        delivery::ack_value(&delivery_value, "rabbit");
+                        break;
+                    }
+                    if app.is_quiting() {
+                        return;
+                    }
+                    let (url, queue, batch, opened) = (url.clone(), queue.clone(), messages.clone(), conn.take());
+                    let result = tokio::task::spawn_blocking(move || { Self::publish_confirmed(opened, url.as_str(), queue.as_str(), &batch, published) }).await;
+                    let (returned, confirmed, result) = match result {
+                        Ok(returned) => returned,
+                        Err(err) => (None, published, Err(Failure::Connection(format!("{:?}", err)))),
+                    };
+                    conn = returned;
+                    published = confirmed;
+                    match result {
+                        Ok(_) => {}
+                        Err(Failure::Connection(err)) => {
+                            println!("rabbit connection failed! error={}", err);
+                            time::sleep(delivery::retry_delay(reconnects)).await;
+                            reconnects += 1;
+                        }
+                        Err(Failure::Publish(err)) if attempt < retries => {
+                            println!("rabbit publish failed! attempt={}, error={}", attempt + 1, err);
+                            time::sleep(delivery::retry_delay(attempt)).await;
+                            attempt += 1;
+                        }
+                        Err(Failure::Publish(err)) => {
+                            // messages kept as dead letters are handled, so the delivery is acknowledged.
+                            Self::dead_letter(&rocks_channel, &delivery_value, &messages[published..], err.as_str());
+                            published = messages.len();
+                        }
                     }
                 }
             }
-            if !app.is_quiting() {
-                Self::recv(conn, monitor, app);
-            }
         });
     }
+
+    /// connects if no connection is given, and publishes the messages from `published` on a new channel.
+    /// returns the connection, none if it is lost, and the count of messages confirmed so far.
+    fn publish_confirmed(mut conn: Option<Connection>, url: &str, queue: &str, messages: &[String], mut published: usize) -> (Option<Connection>, usize, Result<(), Failure>) {
+        if conn.is_none() {
+            match Connection::insecure_open(url) {
+                Ok(opened) => {
+                    println!("rabbit is connected!");
+                    conn = Some(opened);
+                }
+                Err(err) => return (None, published, Err(Failure::Connection(format!("{:?}", err)))),
+            }
+        }
+        let channel = match conn.as_mut().unwrap().open_channel(None) {
+            Ok(channel) => channel,
+            Err(err) => return (None, published, Err(Failure::Connection(format!("{:?}", err)))),
+        };
+        let result = Self::publish(&channel, queue, messages, &mut published);
+        let _ = channel.close();
+        (conn, published, result)
+    }
+
+    /// publishes the messages from `published` with publisher confirms, counting up the ones the broker confirmed in order.
+    /// delivery tags of a new channel count its messages from 1.
+    fn publish(channel: &Channel, queue: &str, messages: &[String], published: &mut usize) -> Result<(), Failure> {
+        let confirms = channel.listen_for_publisher_confirms().map_err(|err| { Failure::Connection(format!("{:?}", err)) })?;
+        channel.enable_publisher_confirms().map_err(|err| { Failure::Connection(format!("{:?}", err)) })?;
+        let exchange = Exchange::direct(channel);
+        for message in messages[*published..].iter() {
+            exchange.publish(Publish::new(message.as_bytes(), queue)).map_err(|err| { Failure::Publish(format!("{:?}", err)) })?;
+        }
+
+        let first = *published;
+        let mut confirmed: u64 = 0;
+        let mut acked: BTreeSet<u64> = BTreeSet::new();
+        while first + (confirmed as usize) < messages.len() {
+            match confirms.recv_timeout(CONFIRM_TIMEOUT) {
+                Ok(Confirm::Ack(payload)) if payload.multiple => confirmed = confirmed.max(payload.delivery_tag),
+                Ok(Confirm::Ack(payload)) => {
+                    acked.insert(payload.delivery_tag);
+                }
+                Ok(Confirm::Nack(payload)) => return Err(Failure::Publish(format!("rabbit message is not confirmed! delivery_tag={}", payload.delivery_tag))),
+                Err(_) => return Err(Failure::Publish(String::from("rabbit confirm timed out!"))),
+            }
+            while acked.remove(&(confirmed + 1)) {
+                confirmed += 1;
+            }
+            *published = first + confirmed as usize;
+        }
+        Ok(())
+    }
+
+    /// messages of a revert, which has no height, are kept at height 0.
+    fn dead_letter(rocks_channel: &channel::Sender, delivery_value: &Value, messages: &[String], error: &str) {
+        let empty = Map::new();
+        let delivery_value = delivery_value.as_object().unwrap_or(&empty);
+        let task_id = get_str(delivery_value, "task_id").unwrap_or("");
+        let height = get_u64(delivery_value, "height").unwrap_or(0);
+        for (idx, message) in messages.iter().enumerate() {
+            let letter = json!({"message": message});
+            delivery::dead_letter(rocks_channel, "rabbit", task_id, height, idx, letter.as_object().unwrap().clone(), error);
+        }
+    }
 }
@@ -9,7 +9,10 @@ use serde_json::{json, Map, Value};
 use crate::{enumeration, message};
 use crate::libs::serde::get_str;
 use crate::plugin::jsonrpc::JsonRpcPlugin;
+use crate::types::channel::next_message;
 use crate::types::enumeration::Enumeration;
+use crate::types::node::Endpoint;
+use crate::types::subscribe::SubscribeTask;
 use crate::validation::find_by_key;
 
 pub struct RocksPlugin {
@@ -49,7 +52,14 @@ impl Plugin for RocksPlugin {
                 return Box::new(futures::future::ok(Value::Object(error)));
             }
             let key = get_str(&params, "key").unwrap();
-            let value = Self::find_by_prefix_static(&db, key);
+            let mut value = Self::find_by_prefix_static(&db, key);
+            if let Value::Object(object) = &mut value {
+                if key.starts_with("task:") {
+                    SubscribeTask::redact(object);
+                } else if key.starts_with("node:") {
+                    Endpoint::redact(object);
+                }
+            }
             Box::new(futures::future::ok(value))
         });
     }
@@ -81,7 +91,7 @@ impl RocksPlugin {
 
     fn recv(db: RocksDB, mut monitor: channel::Receiver, app: QuitHandle) {
         app::spawn(async move {
-            if let Ok(msg) = monitor.try_recv() {
+            while let Some(msg) = next_message(&mut monitor).await {
                 let parsed_msg = msg.as_object().unwrap();
                 let method = RocksMethod::find(parsed_msg.get("method").unwrap().as_str().unwrap()).unwrap();
                 match method {
@@ -95,9 +105,9 @@ impl RocksPlugin {
                         let _ = db.delete(key.as_bytes());
                     }
                 }
-            }
-            if !app.is_quiting() {
-                Self::recv(db, monitor, app);
+                if app.is_quiting() {
+                    break;
+                }
             }
         });
     }
@@ -6,6 +6,7 @@ use serde_json::{json, Value};
 
 use crate::libs::serde::get_str;
 use crate::message;
+use crate::types::channel::next_message;
 
 pub struct SlackPlugin {
     monitor: Option<channel::Receiver>,
@@ -38,7 +39,7 @@ impl Plugin for SlackPlugin {
 impl SlackPlugin {
     fn recv(mut monitor: channel::Receiver, app: QuitHandle) {
         app::spawn(async move {
-            if let Ok(msg) = monitor.try_recv() {
+            while let Some(msg) = next_message(&mut monitor).await {
                 let parsed_msg = msg.as_object().unwrap();
 
                 let slack_hook = get_str(parsed_msg, "slack_hook").unwrap();
@@ -55,9 +56,9 @@ impl SlackPlugin {
                 if let Err(err) = result {
                     println!("slack error={:?}", err);
                 }
-            }
-            if !app.is_quiting() {
-                Self::recv(monitor, app);
+                if app.is_quiting() {
+                    break;
+                }
             }
         });
     }
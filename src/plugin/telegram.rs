@@ -9,6 +9,7 @@ use teloxide::prelude::*;
 use crate::libs;
 use crate::libs::serde::get_str;
 use crate::message;
+use crate::types::channel::next_message;
 
 pub struct TelegramPlugin {
     token: Option<String>,
@@ -54,7 +55,7 @@ impl Plugin for TelegramPlugin {
 impl TelegramPlugin {
     fn recv(mut monitor: channel::Receiver, token: String, app: QuitHandle) {
         app::spawn(async move {
-            if let Ok(msg) = monitor.try_recv() {
+            while let Some(msg) = next_message(&mut monitor).await {
                 let parsed_msg = msg.as_object().unwrap();
                 let chat_id = get_str(parsed_msg, "chat_id").unwrap();
                 let text = get_str(parsed_msg, "text").unwrap();
@@ -71,9 +72,9 @@ impl TelegramPlugin {
                 if let Err(err) = result {
                     println!("telegram_error={:?}", err);
                 }
-            }
-            if !app.is_quiting() {
-                Self::recv(monitor, token, app);
+                if app.is_quiting() {
+                    break;
+                }
             }
         });
     }
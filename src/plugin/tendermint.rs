@@ -1,46 +1,28 @@
-use std::collections::HashMap;
-use std::fs;
-use std::sync::Arc;
-
 use appbase::*;
-use appbase::channel::Sender;
-use appbase::plugin::State;
-use futures::lock::Mutex as FutureMutex;
-use jsonrpc_core::Params;
-use serde::{Deserialize, Serialize};
+use async_trait::async_trait;
 use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
 
-use crate::{enumeration, libs, message};
 use crate::error::error::ExpectedError;
-use crate::libs::mysql::get_params;
+use crate::libs::cosmos;
 use crate::libs::opts::opt_to_result;
 use crate::libs::request;
-use crate::libs::rocks::{get_by_prefix_static, get_static};
-use crate::libs::serde::{get_array, get_object, get_str, get_string, get_value_by_path, select_value};
+use crate::libs::serde::{get_array, get_object, get_str, get_value_by_path};
+use crate::plugin::chain::{ChainAdapter, ChainPlugin};
 use crate::plugin::jsonrpc::JsonRpcPlugin;
-use crate::plugin::mysql::MySqlPlugin;
-use crate::plugin::rocks::{RocksMethod, RocksMsg, RocksPlugin};
-use crate::types::channel::MultiChannel;
+use crate::plugin::node::NodePlugin;
+use crate::plugin::rocks::RocksPlugin;
 use crate::types::enumeration::Enumeration;
-use crate::types::mysql::Schema;
-use crate::types::subscribe::{SubscribeEvent, SubscribeStatus, SubscribeTarget, SubscribeTask};
-use crate::validation::{get_blocks, get_task, get_txs, resubscribe, stop_subscribe, subscribe, unsubscribe};
+use crate::types::subscribe::{SubscribeEvent, SubscribeTarget};
+use crate::validation::tm_subscribe;
 
 pub struct TendermintPlugin {
-    sub_events: Option<SubscribeEvents>,
-    channels: Option<MultiChannel>,
-    monitor: Option<channel::Receiver>,
-    schema: Option<HashMap<String, Schema>>,
+    chain: ChainPlugin<Tendermint>,
 }
 
-const CHAIN: &str = "tendermint";
-const TASK_PREFIX: &str = "task:tendermint";
-
-type SubscribeEvents = Arc<FutureMutex<HashMap<String, SubscribeEvent>>>;
-
-message!((TendermintMsg; {value: Value}); (TendermintMethod; {Subscribe: "subscribe"}, {Resubscribe: "resubscribe"}, {Stop: "stop"}, {Unsubscribe: "unsubscribe"}));
+pub struct Tendermint;
 
-plugin::requires!(TendermintPlugin; JsonRpcPlugin, RocksPlugin);
+plugin::requires!(TendermintPlugin; JsonRpcPlugin, RocksPlugin, NodePlugin);
 
 impl Plugin for TendermintPlugin {
     fn new() -> Self {
@@ -50,469 +32,539 @@ impl Plugin for TendermintPlugin {
         app::arg(clap::Arg::new("tendermint::tx-mongo-sync").long("tm-tx-mongo-sync"));
         app::arg(clap::Arg::new("tendermint::block-rabbit-mq-publish").long("tm-block-rabbit-mq-publish"));
         app::arg(clap::Arg::new("tendermint::tx-rabbit-mq-publish").long("tm-tx-rabbit-mq-publish"));
+        app::arg(clap::Arg::new("tendermint::block_events-mysql-sync").long("tm-block-events-mysql-sync"));
+        app::arg(clap::Arg::new("tendermint::block_events-mongo-sync").long("tm-block-events-mongo-sync"));
+        app::arg(clap::Arg::new("tendermint::block_events-rabbit-mq-publish").long("tm-block-events-rabbit-mq-publish"));
 
         TendermintPlugin {
-            sub_events: None,
-            channels: None,
-            monitor: None,
-            schema: None,
+            chain: ChainPlugin::new(),
         }
     }
 
     fn initialize(&mut self) {
-        self.init();
-        self.register_jsonrpc();
-        self.load_tasks();
-        self.init_mysql();
+        self.chain.initialize();
     }
 
     fn startup(&mut self) {
-        let mut monitor = self.monitor.take().unwrap();
-        let sub_events = Arc::clone(self.sub_events.as_ref().unwrap());
-
-        let mut rocks_channel = self.channels.as_ref().unwrap().get("rocks");
-        let mysql_channel = self.channels.as_ref().unwrap().get("mysql");
-        let rabbit_channel = self.channels.as_ref().unwrap().get("rabbit");
-        let mongo_channel = self.channels.as_ref().unwrap().get("mongo");
+        self.chain.startup();
+    }
 
-        let app = app::quit_handle().unwrap();
+    fn shutdown(&mut self) {
+        self.chain.shutdown();
+    }
+}
 
-        let schema = self.schema.as_ref().unwrap().clone();
-        app::spawn_blocking(move || {
-            loop {
-                if app.is_quiting() {
-                    break;
-                }
-                let sub_events_try_lock = sub_events.try_lock();
-                if sub_events_try_lock.is_none() {
-                    continue;
-                }
-                let mut sub_events_lock = sub_events_try_lock.unwrap();
-                if let Ok(msg) = monitor.try_recv() {
-                    Self::message_handler(&msg, &mut sub_events_lock, &mut rocks_channel);
-                }
+#[async_trait]
+impl ChainAdapter for Tendermint {
+    const CHAIN: &'static str = "tendermint";
+    const PREFIX: &'static str = "tm";
+    const SCHEMA_FILE: &'static str = "schema/tm_mysql.json";
+    const BLOCK_HEIGHT_COLUMN: &'static str = "height";
+    const TX_HEIGHT_COLUMN: &'static str = "height";
+    const TX_HASH_COLUMN: &'static str = "txhash";
+    const TARGETS: &'static [SubscribeTarget] = &[SubscribeTarget::Block, SubscribeTarget::Tx, SubscribeTarget::BlockEvents];
+
+    async fn latest_height(sub_event: &SubscribeEvent, node: &str) -> Result<u64, ExpectedError> {
+        if Self::is_rpc(sub_event) {
+            let result = Self::rpc_get(node, "status", &[]).await?;
+            let height = get_value_by_path(&result, "sync_info.latest_block_height")?;
+            return Ok(opt_to_result(height.as_str())?.parse::<u64>()?);
+        }
+        let req_url = format!("{}/blocks/latest", node);
+        let body = request::get(req_url.as_str()).await?;
+        let height = get_value_by_path(&body, "block.header.height")?;
+        let height = opt_to_result(height.as_str())?.parse::<u64>()?;
+        Ok(height)
+    }
 
-                for (_, sub_event) in sub_events_lock.iter_mut() {
-                    if !sub_event.is_workable() {
-                        continue;
-                    }
-                    if sub_event.target == SubscribeTarget::Block {
-                        let block_header = Self::poll_block_header(sub_event);
-                        match block_header {
-                            Ok(header) => {
-                                // println!("event_id={}, header={}", sub_event.event_id(), header.to_string());
-
-                                let schema_opt = schema.get("tm_block");
-                                let prefix = format!("{}::{}", CHAIN, sub_event.target.value());
-                                if let Err(err) = libs::callback::mysql(prefix.clone(), &header, schema_opt, &mysql_channel) {
-                                    println!("{}", err.to_string());
-                                };
-                                if let Err(err) = libs::callback::mongo(prefix.clone(), &header, "tm_block", &mongo_channel) {
-                                    println!("{}", err.to_string());
-                                };
-                                if let Err(err) = libs::callback::rabbit(prefix, &header, &rabbit_channel) {
-                                    println!("{}", err.to_string());
-                                };
-
-                                Self::sync_event(&rocks_channel, sub_event);
-                                sub_event.curr_height += 1;
-                            }
-                            Err(error) => Self::error_handler(&rocks_channel, sub_event, error)
-                        };
-                    } else {
-                        let block_txs_result = Self::poll_block_txs(sub_event);
-                        match block_txs_result {
-                            Ok(block_txs) => {
-                                if block_txs.len() > 0 {
-                                    let txs_result = Self::poll_txs(sub_event);
-                                    let txs = match txs_result {
-                                        Ok(txs) => txs,
-                                        Err(err) => {
-                                            sub_event.handle_error(&rocks_channel, err.to_string());
-                                            continue;
-                                        }
-                                    };
-
-                                    for tx in txs.iter() {
-                                        let tx_object = tx.as_object().unwrap();
-                                        let filtered_result = libs::serde::filter(tx_object, sub_event.filter.clone());
-                                        if filtered_result.is_err() {
-                                            let err = filtered_result.unwrap_err();
-                                            sub_event.handle_error(&rocks_channel, err.to_string());
-                                            continue;
-                                        } else if filtered_result.is_ok() && !filtered_result.unwrap() {
-                                            continue;
-                                        }
-
-                                        // println!("event_id={}, tx={}", sub_event.event_id(), tx.to_string());
-
-                                        let schema_opt = schema.get("tm_tx");
-                                        let prefix = format!("{}::{}", CHAIN, sub_event.target.value());
-                                        if let Err(err) = libs::callback::mysql(prefix.clone(), &tx, schema_opt, &mysql_channel) {
-                                            println!("{}", err.to_string());
-                                        };
-                                        if let Err(err) = libs::callback::mongo(prefix.clone(), &tx, "tm_tx", &mongo_channel) {
-                                            println!("{}", err.to_string());
-                                        };
-                                        if let Err(err) = libs::callback::rabbit(prefix, &tx, &rabbit_channel) {
-                                            println!("{}", err.to_string());
-                                        };
-                                    }
-                                } else {
-                                    println!("block txs is empty! curr_height={}", sub_event.curr_height);
-                                }
-                                Self::sync_event(&rocks_channel, sub_event);
-                                sub_event.curr_height += 1;
-                            }
-                            Err(error) => Self::error_handler(&rocks_channel, sub_event, error)
-                        }
-                    }
+    async fn fetch_block(sub_event: &SubscribeEvent, node: &str, height: u64) -> Result<Value, ExpectedError> {
+        if Self::is_rpc(sub_event) {
+            return match Self::rpc_get(node, "block", &[("height", height.to_string())]).await {
+                Ok(result) => Ok(Value::Object(get_object(&result, "block")?.clone())),
+                Err(err) if err.to_string().contains("must be less than or equal to the current blockchain height") => {
+                    Err(ExpectedError::BlockHeightError(err.to_string()))
                 }
+                Err(err) => Err(err),
+            };
+        }
+        let req_url = format!("{}/blocks/{}", node, height);
+        let response = request::get(req_url.as_str()).await;
+        let body = match response {
+            Ok(body) => body,
+            Err(err) => {
+                return if err.to_string().starts_with("requested block height") {
+                    Err(ExpectedError::BlockHeightError(err.to_string()))
+                } else {
+                    Err(err)
+                };
             }
-        });
+        };
+        let block = opt_to_result(body.get("block"))?;
+        Ok(block.clone())
     }
 
-    fn shutdown(&mut self) {}
-}
-
-impl TendermintPlugin {
-    fn init(&mut self) {
-        self.sub_events = Some(Arc::new(FutureMutex::new(HashMap::new())));
-        let channels = MultiChannel::new(vec!("tendermint", "rocks", "mysql", "rabbit", "mongo"));
-        self.channels = Some(channels.to_owned());
-        self.monitor = Some(app::subscribe_channel(String::from("tendermint")));
-        self.schema = Some(HashMap::new());
-    }
+    /// `events` of the subscription are added to the query, so the node returns matched txs only.
+    async fn fetch_txs(sub_event: &SubscribeEvent, node: &str, height: u64, block: &Value) -> Result<Vec<Value>, ExpectedError> {
+        let block_object = opt_to_result(block.as_object())?;
+        let txs_value = get_value_by_path(block_object, "data.txs")?;
+        if !txs_value.is_array() {
+            return Err(ExpectedError::TypeError(String::from("txs in block data is not array!")));
+        }
+        if opt_to_result(txs_value.as_array())?.is_empty() {
+            println!("block txs is empty! curr_height={}", height);
+            return Ok(Vec::new());
+        }
+        if Self::is_rpc(sub_event) {
+            return Self::fetch_rpc_txs(sub_event, node, height, block_object).await;
+        }
 
-    fn init_mysql(&mut self) {
-        if let Some(state) = app::plugin_state::<MySqlPlugin>() {
-            if state != State::Initialized {
-                return;
+        let mut query: Vec<(&str, String)> = vec![("events", format!("tx.height={}", height))];
+        if let Some(events) = sub_event.options.get("events") {
+            for event in opt_to_result(events.as_array())?.iter() {
+                query.push(("events", String::from(opt_to_result(event.as_str())?)));
             }
         }
-        let json_str = fs::read_to_string("schema/tm_mysql.json").unwrap();
-        let json_schema: Value = serde_json::from_str(json_str.as_str()).unwrap();
-        let schema_map = json_schema.as_object().unwrap();
-
-        let schema = self.schema.as_mut().unwrap();
-        for (table, values) in schema_map {
-            let created_schema = Schema::from(table.clone(), values).unwrap();
-            schema.insert(table.clone(), created_schema);
+        let req_url = request::url_with_params(format!("{}/cosmos/tx/v1beta1/txs", node).as_str(), &query)?;
+        let body = request::get(req_url.as_str()).await?;
+        if body.get("tx_responses").map_or(true, |txs| { txs.is_null() }) {
+            return Ok(Vec::new());
         }
+        let txs_result = get_array(&body, "tx_responses")?;
+        Ok(txs_result.clone())
+    }
 
-        let plugin_handle = app::get_plugin::<MySqlPlugin>();
-        let mut plugin = plugin_handle.lock().unwrap();
-        let mysql = plugin.downcast_mut::<MySqlPlugin>().unwrap();
-
-        for (_, selected_schema) in schema.iter() {
-            let result = mysql.execute(selected_schema.create_table.clone(), mysql::Params::Empty);
-            if let Err(err) = result {
-                println!("error={}", err.to_string());
+    /// begin, end and finalize block events of `/block_results`, which are not included in any tx.
+    async fn fetch_target(sub_event: &SubscribeEvent, node: &str, height: u64, _: &Value) -> Result<Vec<Value>, ExpectedError> {
+        match sub_event.target {
+            SubscribeTarget::BlockEvents => {
+                let result = Self::rpc_get(node, "block_results", &[("height", height.to_string())]).await?;
+                Ok(Self::block_events(height, &result))
             }
+            _ => Err(ExpectedError::InvalidError(format!("{} target is not supported!", sub_event.target.value()))),
         }
+    }
 
-        let plugin_handle = app::get_plugin::<JsonRpcPlugin>();
-        let mut plugin = plugin_handle.lock().unwrap();
-        let jsonrpc = plugin.downcast_mut::<JsonRpcPlugin>().unwrap();
-
-        let pool = mysql.get_pool();
-        jsonrpc.add_method(String::from("tm_mysql_get_blocks"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = get_blocks::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
-            }
-            let order = get_str(&params, "order").unwrap();
-            let query = format!("select * from tm_block where height >= :from_height and height <= :to_height order by 1 {}", order);
-            let selected_params = select_value(&params, vec!["from_height", "to_height"]).unwrap();
-            let result = MySqlPlugin::query_static(&pool, query, get_params(&selected_params)).unwrap();
-            Box::new(futures::future::ok(Value::Array(result)))
-        });
+    fn options(params: &Map<String, Value>) -> Result<Map<String, Value>, ExpectedError> {
+        tm_subscribe::verify(params)?;
+        let mut options = Map::new();
+        if let Some(events) = params.get("events") {
+            let events = events.as_array().unwrap().iter().map(|event| { Value::String(Self::quote_event(event.as_str().unwrap())) }).collect();
+            options.insert(String::from("events"), Value::Array(events));
+        }
+        if let Some(backend) = params.get("backend") {
+            options.insert(String::from("backend"), backend.clone());
+        }
+        Ok(options)
+    }
 
-        let pool = mysql.get_pool();
-        jsonrpc.add_method(String::from("tm_mysql_get_txs"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = get_txs::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
-            }
-            let (query, selected_params) = if params.get("txhash").is_some() {
-                let query = String::from("select * from tm_tx where txhash=:txhash");
-                let selected_params = select_value(&params, vec!["txhash"]).unwrap();
-                (query, selected_params)
-            } else {
-                let order = get_str(&params, "order").unwrap();
-                let query = format!("select * from tm_tx where height >= :from_height and height <= :to_height order by 1 {}", order);
-                let selected_params = select_value(&params, vec!["from_height", "to_height"]).unwrap();
-                (query, selected_params)
-            };
-            let result = MySqlPlugin::query_static(&pool, query, get_params(&selected_params)).unwrap();
-            Box::new(futures::future::ok(Value::Array(result)))
-        });
+    /// new heads of CometBFT RPC `/websocket`.
+    fn head_subscription() -> Option<Value> {
+        Some(json!({"jsonrpc": "2.0", "id": 1, "method": "subscribe", "params": {"query": "tm.event='NewBlock'"}}))
     }
 
-    fn register_jsonrpc(&self) {
-        let plugin_handle = app::get_plugin::<JsonRpcPlugin>();
-        let mut plugin = plugin_handle.lock().unwrap();
-        let jsonrpc = plugin.downcast_mut::<JsonRpcPlugin>().unwrap();
-
-        let plugin_handle = app::get_plugin::<RocksPlugin>();
-        let mut plugin = plugin_handle.lock().unwrap();
-        let rocks = plugin.downcast_mut::<RocksPlugin>().unwrap();
-
-        let tm_channel = self.channels.as_ref().unwrap().get("tendermint");
-        let rocks_db = rocks.get_db();
-        jsonrpc.add_method(String::from("tm_subscribe"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = subscribe::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
-            }
-            let task_id = SubscribeTask::task_id(CHAIN, &params);
-            let value = get_static(&rocks_db, task_id.as_str());
-            if value.is_null() {
-                let message = TendermintMsg::new(TendermintMethod::Subscribe, Value::Object(params.clone()));
-                let _ = tm_channel.send(message);
-
-                Box::new(futures::future::ok(Value::String(format!("subscription requested! task_id={}", task_id))))
-            } else {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(format!("already exist task! task_id={}", task_id)));
-                Box::new(futures::future::ok(Value::Object(error)))
-            }
-        });
+    fn head_notification(message: &Value) -> Option<u64> {
+        let height = message.pointer("/result/data/value/block/header/height")?.as_str()?;
+        height.parse::<u64>().ok()
+    }
 
-        let tm_channel = self.channels.as_ref().unwrap().get("tendermint");
-        let rocks_db = rocks.get_db();
-        jsonrpc.add_method(String::from("tm_unsubscribe"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = unsubscribe::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
-            }
+    fn table(target: &SubscribeTarget) -> String {
+        match target {
+            SubscribeTarget::BlockEvents => String::from("tm_block_event"),
+            _ => format!("{}_{}", Self::PREFIX, target.value()),
+        }
+    }
 
-            let task_id = get_str(&params, "task_id").unwrap();
-            let value = get_static(&rocks_db, task_id);
-            if value.is_null() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(format!("task does not exist! task_id={}", task_id)));
-                Box::new(futures::future::ok(Value::Object(error)))
-            } else {
-                let tm_msg = TendermintMsg::new(TendermintMethod::Unsubscribe, Value::Object(params.clone()));
-                let _ = tm_channel.send(tm_msg);
-
-                Box::new(futures::future::ok(Value::String(format!("unsubscription requested! task_id={}", task_id))))
-            }
-        });
+    fn block_record(block: &Value) -> Result<Value, ExpectedError> {
+        let header = opt_to_result(block.get("header"))?;
+        if !header.is_object() {
+            return Err(ExpectedError::TypeError(String::from("block header is not object!")));
+        }
+        Ok(header.clone())
+    }
 
-        let tm_channel = self.channels.as_ref().unwrap().get("tendermint");
-        let rocks_db = rocks.get_db();
-        jsonrpc.add_method(String::from("tm_resubscribe"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = resubscribe::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
+    /// tx with the attributes of `logs[].events[]` merged by event type, so filters can use `transfer.recipient`.
+    /// event types already in the tx(ex. `tx`) get only the attributes it does not have.
+    fn filter_view(value: &Value) -> Option<Value> {
+        let mut view = value.as_object()?.clone();
+        for (event_type, attributes) in Self::flatten_events(&view).into_iter() {
+            match view.get_mut(&event_type) {
+                Some(Value::Object(existing)) => {
+                    for (key, value) in attributes.into_iter() {
+                        existing.entry(key).or_insert(value);
+                    }
+                }
+                Some(_) => {}
+                None => {
+                    view.insert(event_type, Value::Object(attributes));
+                }
             }
+        }
+        Some(Value::Object(view))
+    }
+}
 
-            let task_id = get_str(&params, "task_id").unwrap();
-            let value = get_static(&rocks_db, task_id);
-            if value.is_null() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(format!("subscription does not exist! task_id={}", task_id)));
-                Box::new(futures::future::ok(Value::Object(error)))
-            } else {
-                let message = TendermintMsg::new(TendermintMethod::Resubscribe, Value::Object(params.clone()));
-                let _ = tm_channel.send(message);
-
-                Box::new(futures::future::ok(Value::String(format!("resubscription requested! task_id={}", task_id))))
-            }
-        });
+impl Tendermint {
+    /// `"backend": "rpc"` uses CometBFT RPC(port 26657) instead of LCD. `block_events` target is served by RPC only.
+    /// its txs are decoded by `libs::cosmos`, where messages of unknown types keep base64 `value` instead of the fields of LCD.
+    fn is_rpc(sub_event: &SubscribeEvent) -> bool {
+        sub_event.target == SubscribeTarget::BlockEvents || sub_event.options.get("backend").and_then(|backend| { backend.as_str() }) == Some("rpc")
+    }
 
-        let tm_channel = self.channels.as_ref().unwrap().get("tendermint");
-        let rocks_db = rocks.get_db();
-        jsonrpc.add_method(String::from("tm_stop_subscription"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = stop_subscribe::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
-            }
+    /// result of CometBFT RPC over URI. string params must be quoted. ex) `query="tx.height=5"`
+    async fn rpc_get(node: &str, method: &str, params: &[(&str, String)]) -> Result<Map<String, Value>, ExpectedError> {
+        let req_url = request::url_with_params(format!("{}/{}", node, method).as_str(), params)?;
+        let body = request::get(req_url.as_str()).await?;
+        if body.get("error").map_or(false, |error| { !error.is_null() }) {
+            return Err(ExpectedError::RequestError(request::error_message(&body)?));
+        }
+        Ok(get_object(&body, "result")?.clone())
+    }
 
-            let task_id = get_str(&params, "task_id").unwrap();
-            let value = get_static(&rocks_db, task_id);
-            if value.is_null() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(format!("task does not exist! task_id={}", task_id)));
-                Box::new(futures::future::ok(Value::Object(error)))
-            } else {
-                let tm_msg = TendermintMsg::new(TendermintMethod::Stop, Value::Object(params.clone()));
-                let _ = tm_channel.send(tm_msg);
-
-                Box::new(futures::future::ok(Value::String(format!("stop subscription requested! task_id={}", task_id))))
+    /// txs of CometBFT RPC in the shape of LCD `tx_responses`.
+    /// `events` of the subscription are searched by `/tx_search`, otherwise `/block_results` is matched with the block txs.
+    async fn fetch_rpc_txs(sub_event: &SubscribeEvent, node: &str, height: u64, block: &Map<String, Value>) -> Result<Vec<Value>, ExpectedError> {
+        let timestamp = get_value_by_path(block, "header.time")?;
+        let mut txs: Vec<Value> = Vec::new();
+        if let Some(events) = sub_event.options.get("events") {
+            let mut query = vec![format!("tx.height={}", height)];
+            for event in opt_to_result(events.as_array())?.iter() {
+                query.push(String::from(opt_to_result(event.as_str())?));
             }
-        });
-
-        let rocks_db = rocks.get_db();
-        jsonrpc.add_method(String::from("tm_get_tasks"), move |params: Params| {
-            let params: Map<String, Value> = params.parse().unwrap();
-            let verified = get_task::verify(&params);
-            if verified.is_err() {
-                let mut error = Map::new();
-                error.insert(String::from("error"), Value::String(verified.unwrap_err().to_string()));
-                return Box::new(futures::future::ok(Value::Object(error)));
+            let query = format!("\"{}\"", query.join(" AND "));
+            let mut page = 1;
+            loop {
+                let params = [("query", query.clone()), ("page", page.to_string()), ("per_page", String::from("100")), ("order_by", String::from("\"asc\""))];
+                let result = Self::rpc_get(node, "tx_search", &params).await?;
+                let searched = get_array(&result, "txs")?;
+                for tx in searched.iter() {
+                    let tx = opt_to_result(tx.as_object())?;
+                    txs.push(Self::tx_response(height, get_str(tx, "hash")?, get_str(tx, "tx")?, get_object(tx, "tx_result")?, timestamp)?);
+                }
+                let total_count = get_str(&result, "total_count")?.parse::<usize>()?;
+                if searched.is_empty() || txs.len() >= total_count {
+                    break;
+                }
+                page += 1;
             }
+            return Ok(txs);
+        }
 
-            let prefix = match params.get("task_id") {
-                None => TASK_PREFIX,
-                Some(task_id) => task_id.as_str().unwrap(),
-            };
-            let tasks = get_by_prefix_static(&rocks_db, prefix);
-            Box::new(futures::future::ok(tasks))
-        });
+        let block_txs = opt_to_result(get_value_by_path(block, "data.txs")?.as_array())?;
+        let result = Self::rpc_get(node, "block_results", &[("height", height.to_string())]).await?;
+        let tx_results = get_array(&result, "txs_results")?;
+        if tx_results.len() != block_txs.len() {
+            return Err(ExpectedError::InvalidError(format!("txs_results does not match block txs! height={}", height)));
+        }
+        for (tx, tx_result) in block_txs.iter().zip(tx_results.iter()) {
+            let tx = opt_to_result(tx.as_str())?;
+            let hash = Self::tx_hash(tx)?;
+            txs.push(Self::tx_response(height, hash.as_str(), tx, opt_to_result(tx_result.as_object())?, timestamp)?);
+        }
+        Ok(txs)
     }
 
-    fn load_tasks(&self) {
-        let plugin_handle = app::get_plugin::<RocksPlugin>();
-        let mut plugin = plugin_handle.lock().unwrap();
-        let rocks = plugin.downcast_mut::<RocksPlugin>().unwrap();
+    /// LCD tx response of a base64 tx and its `ResponseDeliverTx`. `logs` are parsed from the log of succeeded txs like LCD.
+    fn tx_response(height: u64, hash: &str, tx: &str, tx_result: &Map<String, Value>, timestamp: &Value) -> Result<Value, ExpectedError> {
+        let code = tx_result.get("code").and_then(|code| { code.as_u64() }).unwrap_or(0);
+        let raw_log = tx_result.get("log").and_then(|log| { log.as_str() }).unwrap_or("");
+        let logs = match serde_json::from_str::<Value>(raw_log) {
+            Ok(Value::Array(logs)) if code == 0 => Value::Array(logs),
+            _ => json!([]),
+        };
+        let field = |name: &str, default: Value| { tx_result.get(name).filter(|value| { !value.is_null() }).cloned().unwrap_or(default) };
+        Ok(json!({
+            "height": height.to_string(),
+            "txhash": hash,
+            "codespace": field("codespace", json!("")),
+            "code": code,
+            "data": field("data", json!("")),
+            "raw_log": raw_log,
+            "logs": logs,
+            "info": field("info", json!("")),
+            "gas_wanted": field("gas_wanted", json!("0")),
+            "gas_used": field("gas_used", json!("0")),
+            "tx": cosmos::decode_tx(tx)?,
+            "timestamp": timestamp,
+            "events": field("events", json!([]))
+        }))
+    }
 
-        let rocks_db = rocks.get_db();
-        let raw_tasks = get_by_prefix_static(&rocks_db, TASK_PREFIX);
-        let sub_events = Arc::clone(self.sub_events.as_ref().unwrap());
-        let mut sub_events_lock = sub_events.try_lock().unwrap();
-        for raw_task in raw_tasks.as_array().unwrap() {
-            let task = raw_task.as_object().unwrap();
-            let event = SubscribeEvent::from(task);
-            sub_events_lock.insert(event.task_id.clone(), event);
+    /// uppercase hex of the sha256 of tx bytes, same as `txhash` of LCD.
+    fn tx_hash(tx: &str) -> Result<String, ExpectedError> {
+        let bytes = match base64::decode(tx) {
+            Ok(bytes) => bytes,
+            Err(err) => return Err(ExpectedError::ParsingError(format!("tx is not base64! error={}", err))),
+        };
+        Ok(Sha256::digest(&bytes).iter().map(|byte| { format!("{:02X}", byte) }).collect())
+    }
+
+    /// LCD needs a quoted value. ex) `transfer.recipient=cosmos1...` to `transfer.recipient='cosmos1...'`
+    fn quote_event(event: &str) -> String {
+        match event.split_once('=') {
+            Some((key, value)) if !value.starts_with('\'') => format!("{}='{}'", key, value),
+            _ => String::from(event),
         }
     }
 
-    fn sync_event(rocks_channel: &channel::Sender, sub_event: &mut SubscribeEvent) {
-        let task = SubscribeTask::from(&sub_event, String::from(""));
-        let task_id = task.task_id.clone();
+    /// attributes of `logs[].events[]` by event type. a repeated attribute becomes an array.
+    /// Cosmos SDK 0.50 or later leaves `logs` empty, so the top level `events` are taken instead.
+    /// ex) `{"transfer": {"recipient": ["cosmos1...", "cosmos1..."], "amount": "100uatom"}}`
+    fn flatten_events(tx: &Map<String, Value>) -> Vec<(String, Map<String, Value>)> {
+        let logs = tx.get("logs").and_then(|logs| { logs.as_array() });
+        let mut events: Vec<&Value> = logs.into_iter().flatten().flat_map(|log| {
+            log.get("events").and_then(|events| { events.as_array() }).into_iter().flatten()
+        }).collect();
+        if events.is_empty() {
+            events = tx.get("events").and_then(|events| { events.as_array() }).into_iter().flatten().collect();
+        }
 
-        let msg = RocksMsg::new(RocksMethod::Put, task_id, Value::String(json!(task).to_string()));
-        let _ = rocks_channel.send(msg);
+        let mut flattened: Vec<(String, Map<String, Value>)> = Vec::new();
+        for event in events.into_iter() {
+            let event_type = match event.get("type").and_then(|event_type| { event_type.as_str() }) {
+                Some(event_type) => event_type,
+                None => continue,
+            };
+            let idx = match flattened.iter().position(|(flattened_type, _)| { flattened_type == event_type }) {
+                Some(idx) => idx,
+                None => {
+                    flattened.push((String::from(event_type), Map::new()));
+                    flattened.len() - 1
+                }
+            };
+            Self::merge_attributes(&mut flattened[idx].1, event);
+        }
+        flattened
     }
 
-    fn error_handler(rocks_channel: &channel::Sender, sub_event: &mut SubscribeEvent, error: ExpectedError) {
-        match error {
-            ExpectedError::BlockHeightError(err_msg) => println!("{}", err_msg),
-            ExpectedError::FilterError(err_msg) => {
-                println!("{}", err_msg);
-                Self::sync_event(&rocks_channel, sub_event);
-                sub_event.curr_height += 1;
-            }
-            _ => {
-                sub_event.handle_error(&rocks_channel, error.to_string());
+    /// records of `begin_block_events`, `end_block_events` and `finalize_block_events`(CometBFT 0.38) of `/block_results`.
+    /// ex) `{"height": "5", "stage": "begin_block", "event_index": 0, "type": "rewards", "attributes": {"amount": "10uatom"}}`
+    fn block_events(height: u64, block_results: &Map<String, Value>) -> Vec<Value> {
+        let mut records: Vec<Value> = Vec::new();
+        for stage in ["begin_block", "end_block", "finalize_block"].iter() {
+            let events = block_results.get(format!("{}_events", stage).as_str()).and_then(|events| { events.as_array() });
+            for (idx, event) in events.into_iter().flatten().enumerate() {
+                let mut attributes = Map::new();
+                Self::merge_attributes(&mut attributes, event);
+                records.push(json!({
+                    "height": height.to_string(),
+                    "stage": stage,
+                    "event_index": idx,
+                    "type": event.get("type").and_then(|event_type| { event_type.as_str() }).unwrap_or(""),
+                    "attributes": attributes
+                }));
             }
-        };
+        }
+        records
     }
 
-    fn message_handler(msg: &Value, sub_events: &mut HashMap<String, SubscribeEvent>, rocks_channel: &mut Sender) {
-        let parsed_msg = msg.as_object().unwrap();
-        let method = TendermintMethod::find(get_str(parsed_msg, "method").unwrap()).unwrap();
-        let params = get_object(parsed_msg, "value").unwrap();
-        match method {
-            TendermintMethod::Subscribe => {
-                let new_event = SubscribeEvent::new(CHAIN, &params);
-                sub_events.insert(new_event.task_id.clone(), new_event.clone());
-
-                let task = SubscribeTask::from(&new_event, String::from(""));
-                let msg = RocksMsg::new(RocksMethod::Put, new_event.task_id, Value::String(json!(task).to_string()));
-                let _ = rocks_channel.send(msg);
+    /// decoded attributes of the event added to `attributes`. a repeated attribute becomes an array.
+    fn merge_attributes(attributes: &mut Map<String, Value>, event: &Value) {
+        let event_attributes = event.get("attributes").and_then(|attributes| { attributes.as_array() });
+        for attribute in event_attributes.into_iter().flatten() {
+            let key = match attribute.get("key").and_then(|key| { key.as_str() }) {
+                Some(key) => key,
+                None => continue,
+            };
+            let value = attribute.get("value").and_then(|value| { value.as_str() }).unwrap_or("");
+            let (key, value) = match Self::decode_base64(key) {
+                Some(decoded_key) => (decoded_key, Self::decode_base64(value).unwrap_or_else(|| { String::from(value) })),
+                None => (String::from(key), String::from(value)),
+            };
+            match attributes.get_mut(&key) {
+                Some(Value::Array(values)) => values.push(Value::String(value)),
+                Some(existing) => *existing = Value::Array(vec![existing.clone(), Value::String(value)]),
+                None => {
+                    attributes.insert(key, Value::String(value));
+                }
             }
-            TendermintMethod::Unsubscribe => {
-                let task_id = get_string(&params, "task_id").unwrap();
-                sub_events.remove(&task_id);
+        }
+    }
 
-                let msg = RocksMsg::new(RocksMethod::Delete, task_id, Value::Null);
-                let _ = rocks_channel.send(msg);
-            }
-            TendermintMethod::Resubscribe => {
-                let task_id = get_str(&params, "task_id").unwrap();
-                let mut sub_event = sub_events.get(task_id).unwrap().clone();
-                sub_event.node_idx = 0;
-                sub_event.status = SubscribeStatus::Working;
-                sub_events.insert(sub_event.task_id.clone(), sub_event.clone());
-
-                let task = SubscribeTask::from(&sub_event, String::from(""));
-                let msg = RocksMsg::new(RocksMethod::Put, sub_event.task_id, Value::String(json!(task).to_string()));
-                let _ = rocks_channel.send(msg);
-            }
-            TendermintMethod::Stop => {
-                let task_id = get_str(&params, "task_id").unwrap();
-                let mut sub_event = sub_events.get(task_id).unwrap().clone();
-                sub_event.status = SubscribeStatus::Stopped;
-                sub_events.insert(sub_event.task_id.clone(), sub_event.clone());
-
-                let task = SubscribeTask::from(&sub_event, String::from(""));
-                let msg = RocksMsg::new(RocksMethod::Put, sub_event.task_id, Value::String(json!(task).to_string()));
-                let _ = rocks_channel.send(msg);
-            }
-        };
+    /// attributes of older chains are base64 encoded. a text is taken as encoded only if it decodes to printable ascii.
+    fn decode_base64(text: &str) -> Option<String> {
+        let decoded = String::from_utf8(base64::decode(text).ok()?).ok()?;
+        if decoded.is_empty() || !decoded.chars().all(|c| { c.is_ascii_graphic() }) {
+            return None;
+        }
+        Some(decoded)
     }
+}
 
-    fn poll_block(sub_event: &mut SubscribeEvent) -> Result<Value, ExpectedError> {
-        let node_index = usize::from(sub_event.node_idx);
-        let req_url = format!("{}/blocks/{}", sub_event.nodes[node_index], sub_event.curr_height);
-        let response = request::get(req_url.as_str());
-        let body = match response {
-            Ok(body) => body,
-            Err(err) => {
-                return if err.to_string().starts_with("requested block height") {
-                    Err(ExpectedError::BlockHeightError(err.to_string()))
-                } else {
-                    Err(err)
-                };
+#[cfg(test)]
+mod tendermint_test {
+    use serde_json::{json, Value};
+
+    use crate::error::error::ExpectedError;
+    use crate::libs::cosmos::cosmos_test::{SEND_TX, SEND_TX_HASH};
+    use crate::libs::filter::Filter;
+    use crate::libs::mock;
+    use crate::plugin::chain::ChainAdapter;
+    use crate::plugin::tendermint::Tendermint;
+    use crate::types::subscribe::SubscribeEvent;
+
+    fn tx_result() -> Value {
+        json!({
+            "code": 0,
+            "data": "",
+            "log": "[{\"msg_index\":0,\"events\":[{\"type\":\"transfer\",\"attributes\":[{\"key\":\"recipient\",\"value\":\"cosmos1to\"}]}]}]",
+            "gas_wanted": "200000",
+            "gas_used": "80000",
+            "events": [{"type": "transfer", "attributes": [{"key": "recipient", "value": "cosmos1to", "index": true}]}],
+            "codespace": ""
+        })
+    }
+
+    /// CometBFT RPC over URI whose latest height is 5.
+    fn handle(path: &str) -> (u16, Value) {
+        let result = match path.split('?').next().unwrap() {
+            "/status" => json!({"sync_info": {"latest_block_height": "5"}}),
+            "/block" if path.contains("height=5") => json!({"block_id": {}, "block": {
+                "header": {"height": "5", "time": "2021-08-01T00:00:00Z"},
+                "data": {"txs": [SEND_TX]}
+            }}),
+            "/block" => {
+                return (500, json!({"jsonrpc": "2.0", "id": -1, "error": {"code": -32603, "message": "Internal error", "data": "height 6 must be less than or equal to the current blockchain height 5"}}));
             }
+            "/block_results" => json!({
+                "height": "5",
+                "txs_results": [tx_result()],
+                "begin_block_events": [{"type": "rewards", "attributes": [{"key": "YW1vdW50", "value": "MTB1YXRvbQ==", "index": true}]}],
+                "end_block_events": null
+            }),
+            "/tx_search" => json!({"txs": [{"hash": SEND_TX_HASH, "height": "5", "index": 0, "tx_result": tx_result(), "tx": SEND_TX}], "total_count": "1"}),
+            _ => return (404, json!({"error": "not found"})),
         };
-        let block = opt_to_result(body.get("block"))?;
-        Ok(block.clone())
+        (200, json!({"jsonrpc": "2.0", "id": -1, "result": result}))
     }
 
-    fn poll_block_header(sub_event: &mut SubscribeEvent) -> Result<Value, ExpectedError> {
-        let block_value = Self::poll_block(sub_event)?;
-        let block = opt_to_result(block_value.as_object())?;
-        let header = opt_to_result(block.get("header"))?;
-        let header_object = opt_to_result(header.as_object())?;
+    fn mock_node() -> String {
+        mock::mock_json_node(|request| { handle(request.path.as_str()) })
+    }
 
-        let filter_result = libs::serde::filter(header_object, sub_event.filter.clone())?;
-        if !filter_result {
-            return Err(ExpectedError::FilterError(String::from("value does not match on filter condition!")));
+    fn sub_event(target: &str, events: Option<Value>) -> SubscribeEvent {
+        let mut params = json!({"sub_id": "cosmoshub", "start_height": 5u64, "target": target, "nodes": ["http://127.0.0.1:26657"], "backend": "rpc"});
+        if let Some(events) = events {
+            params["events"] = events;
         }
-        Ok(header.clone())
+        let mut sub_event = mock::sub_event("tendermint", params.clone());
+        sub_event.options = Tendermint::options(params.as_object().unwrap()).unwrap();
+        sub_event
     }
 
-    fn poll_block_txs(sub_event: &mut SubscribeEvent) -> Result<Vec<Value>, ExpectedError> {
-        let block_value = Self::poll_block(sub_event)?;
-        let block = opt_to_result(block_value.as_object())?;
-        let txs_value = get_value_by_path(block, "data.txs")?;
-        if !txs_value.is_array() {
-            return Err(ExpectedError::TypeError(String::from("txs in block data is not array!")));
+    #[tokio::test]
+    async fn rpc_latest_height_test() {
+        let node = mock_node();
+        let height = Tendermint::latest_height(&sub_event("block", None), node.as_str()).await.unwrap();
+        assert_eq!(height, 5);
+    }
+
+    #[tokio::test]
+    async fn rpc_fetch_block_test() {
+        let node = mock_node();
+        let block = Tendermint::fetch_block(&sub_event("block", None), node.as_str(), 5).await.unwrap();
+        assert_eq!(Tendermint::block_record(&block).unwrap(), json!({"height": "5", "time": "2021-08-01T00:00:00Z"}));
+
+        let result = Tendermint::fetch_block(&sub_event("block", None), node.as_str(), 6).await;
+        assert!(matches!(result, Err(ExpectedError::BlockHeightError(_))));
+    }
+
+    #[tokio::test]
+    async fn rpc_fetch_txs_test() {
+        let node = mock_node();
+        for events in [None, Some(json!(["transfer.recipient=cosmos1to"]))].iter() {
+            let sub_event = sub_event("tx", events.clone());
+            let block = Tendermint::fetch_block(&sub_event, node.as_str(), 5).await.unwrap();
+            let txs = Tendermint::fetch_txs(&sub_event, node.as_str(), 5, &block).await.unwrap();
+            assert_eq!(txs.len(), 1);
+            assert_eq!(txs[0]["txhash"], SEND_TX_HASH);
+            assert_eq!(txs[0]["height"], "5");
+            assert_eq!(txs[0]["timestamp"], "2021-08-01T00:00:00Z");
+            assert_eq!(txs[0]["tx"]["body"]["messages"][0]["to_address"], "cosmos1to");
+            assert_eq!(Tendermint::filter_view(&txs[0]).unwrap()["transfer"], json!({"recipient": "cosmos1to"}));
         }
-        let txs = opt_to_result(txs_value.as_array())?;
+    }
 
-        Ok(txs.clone())
+    #[test]
+    fn tendermint_filter_view_test() {
+        let tx = json!({
+            "txhash": "ABC",
+            "tx": {"@type": "/cosmos.tx.v1beta1.Tx"},
+            "logs": [{"events": [
+                {"type": "message", "attributes": [{"key": "action", "value": "/cosmos.bank.v1beta1.MsgSend"}]},
+                {"type": "transfer", "attributes": [
+                    {"key": "recipient", "value": "cosmos1abc"},
+                    {"key": "amount", "value": "100uatom"},
+                    {"key": "recipient", "value": "cosmos1def"}
+                ]},
+                {"type": "tx", "attributes": [{"key": "ZmVl", "value": "MTB1YXRvbQ=="}]}
+            ]}]
+        });
+        let view = Tendermint::filter_view(&tx).unwrap();
+        assert_eq!(view["message"], json!({"action": "/cosmos.bank.v1beta1.MsgSend"}));
+        assert_eq!(view["transfer"], json!({"recipient": ["cosmos1abc", "cosmos1def"], "amount": "100uatom"}));
+        assert_eq!(view["tx"], json!({"@type": "/cosmos.tx.v1beta1.Tx", "fee": "10uatom"}));
     }
 
-    fn poll_txs(sub_event: &mut SubscribeEvent) -> Result<Vec<Value>, ExpectedError> {
-        let node_index = usize::from(sub_event.node_idx);
-        let req_url = format!("{}/cosmos/tx/v1beta1/txs?events=tx.height={}", sub_event.nodes[node_index], sub_event.curr_height);
-        let body = request::get(req_url.as_str())?;
+    #[test]
+    fn tendermint_filter_view_without_logs_test() {
+        let tx = json!({
+            "txhash": "ABC",
+            "logs": [],
+            "events": [
+                {"type": "transfer", "attributes": [
+                    {"key": "recipient", "value": "cosmos1abc", "index": true},
+                    {"key": "amount", "value": "100uatom", "index": true}
+                ]}
+            ]
+        });
+        let view = Tendermint::filter_view(&tx).unwrap();
+        assert_eq!(view["transfer"], json!({"recipient": "cosmos1abc", "amount": "100uatom"}));
 
-        let txs_result = get_array(&body, "tx_responses")?;
-        Ok(txs_result.clone())
+        let filter = Filter::parse("transfer.recipient = cosmos1abc", false).unwrap();
+        assert!(filter.matches(view.as_object().unwrap()).unwrap());
+    }
+
+    #[tokio::test]
+    async fn rpc_fetch_block_events_test() {
+        let node = mock_node();
+        let sub_event = sub_event("block_events", None);
+        let block = Tendermint::fetch_block(&sub_event, node.as_str(), 5).await.unwrap();
+        let events = Tendermint::fetch_target(&sub_event, node.as_str(), 5, &block).await.unwrap();
+        assert_eq!(events, vec![json!({"height": "5", "stage": "begin_block", "event_index": 0, "type": "rewards", "attributes": {"amount": "10uatom"}})]);
+    }
+
+    #[test]
+    fn tendermint_block_events_test() {
+        let block_results = json!({
+            "height": "7",
+            "txs_results": null,
+            "finalize_block_events": [
+                {"type": "commission", "attributes": [{"key": "amount", "value": "1uatom"}, {"key": "mode", "value": "BeginBlock"}]},
+                {"type": "transfer", "attributes": [{"key": "recipient", "value": "cosmos1a"}, {"key": "recipient", "value": "cosmos1b"}]}
+            ]
+        });
+        let events = Tendermint::block_events(7, block_results.as_object().unwrap());
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["stage"], "finalize_block");
+        assert_eq!(events[0]["attributes"], json!({"amount": "1uatom", "mode": "BeginBlock"}));
+        assert_eq!(events[1]["event_index"], 1);
+        assert_eq!(events[1]["attributes"], json!({"recipient": ["cosmos1a", "cosmos1b"]}));
+    }
+
+    #[test]
+    fn tendermint_head_notification_test() {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {"query": "tm.event='NewBlock'", "data": {"type": "tendermint/event/NewBlock", "value": {"block": {"header": {"height": "7364552"}}}}}
+        });
+        assert_eq!(Tendermint::head_notification(&notification), Some(7364552));
+        assert_eq!(Tendermint::head_notification(&json!({"jsonrpc": "2.0", "id": 1, "result": {}})), None);
+    }
+
+    #[test]
+    fn tendermint_quote_event_test() {
+        assert_eq!(Tendermint::quote_event("transfer.recipient=cosmos1abc"), "transfer.recipient='cosmos1abc'");
+        assert_eq!(Tendermint::quote_event("message.action='/cosmos.bank.v1beta1.MsgSend'"), "message.action='/cosmos.bank.v1beta1.MsgSend'");
     }
 }
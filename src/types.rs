@@ -3,3 +3,4 @@ pub mod channel;
 pub mod enumeration;
 pub mod message;
 pub mod mysql;
+pub mod node;
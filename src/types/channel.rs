@@ -1,5 +1,11 @@
 use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
 use appbase::{channel, app};
+use serde_json::Value;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
 
 #[derive(Clone)]
 pub struct MultiChannel {
@@ -21,3 +27,47 @@ impl MultiChannel {
         self.channel_map.get(name).unwrap().clone()
     }
 }
+
+/// waits for the next message without polling. messages missed by a lagged receiver are skipped.
+pub async fn next_message(monitor: &mut channel::Receiver) -> Option<Value> {
+    loop {
+        match monitor.recv().await {
+            Ok(msg) => return Some(msg),
+            Err(RecvError::Lagged(skipped)) => println!("channel lagged! skipped={}", skipped),
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// queues of sink messages by task id, each handled in order by its own worker.
+/// a task waiting to retry a write does not hold the messages of the other tasks.
+pub struct TaskQueues<F> {
+    queues: HashMap<String, mpsc::UnboundedSender<Value>>,
+    handler: Arc<F>,
+}
+
+impl<F, Fut> TaskQueues<F> where F: Fn(Value) -> Fut + Send + Sync + 'static, Fut: Future<Output = ()> + Send + 'static {
+    pub fn new(handler: F) -> Self {
+        TaskQueues {
+            queues: HashMap::new(),
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// the worker of a task starts on its first message.
+    pub fn send(&mut self, task_id: &str, msg: Value) {
+        let handler = self.handler.clone();
+        let queue = self.queues.entry(String::from(task_id)).or_insert_with(|| {
+            let (sender, mut receiver) = mpsc::unbounded_channel::<Value>();
+            app::spawn(async move {
+                while let Some(msg) = receiver.recv().await {
+                    handler(msg).await;
+                }
+            });
+            sender
+        });
+        if let Err(err) = queue.send(msg) {
+            println!("task queue is closed! task_id={}, error={}", task_id, err.to_string());
+        }
+    }
+}
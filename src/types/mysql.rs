@@ -1,11 +1,14 @@
 use std::fmt::Debug;
 
 use jsonrpc_core::Value;
+use serde_json::Map;
+use sha2::{Digest, Sha256};
 
 use crate::enumeration;
 use crate::error::error::ExpectedError;
-use crate::libs::mysql::convert_type;
-use crate::libs::serde::{get_array, get_object};
+use crate::libs::mysql::{column_type, datetime, uint256};
+use crate::libs::path::Path;
+use crate::libs::serde::{get_array, get_bool, get_object, get_str};
 use crate::types::enumeration::Enumeration;
 
 #[derive(Clone, Debug)]
@@ -13,15 +16,29 @@ pub struct Schema {
     pub table: String,
     pub attributes: Vec<Attribute>,
     pub create_table: String,
+    /// `insert ... on duplicate key update` if `upsert`, so rows written again replace the ones of the same unique key.
     pub insert_query: String,
+    /// true if the schema sets `"upsert": true`. it requires `uniques`, and the first one is the natural key.
+    pub upsert: bool,
+    pub uniques: Vec<Vec<String>>,
+    pub indexes: Vec<Vec<String>>,
+    /// sha256 of the table in the schema file. a migration is applied once for each version.
+    pub version: String,
 }
 
 #[derive(Clone, Debug)]
 pub struct Attribute {
     pub name: String,
-    _type: String,
-    max_length: Option<u32>,
+    /// path of the column value, given by `path` or `source`. `name` if the attribute has neither.
+    pub path: Path,
+    /// ex) "varchar(100)", "decimal(78,0)", "int unsigned"
+    column_type: String,
     nullable: bool,
+    format: Option<Format>,
+    /// sql literal of `default`. ex) "'0x'", "0", "null"
+    default: Option<String>,
+    /// `description` kept as the column comment.
+    description: Option<String>,
 }
 
 impl Schema {
@@ -31,81 +48,75 @@ impl Schema {
         }
         let map = values.as_object().unwrap();
         let raw_attributes = get_object(map, "attributes")?;
+        let strict_paths = get_bool(map, "strict_paths").unwrap_or(false);
 
         let mut attributes: Vec<Attribute> = Vec::new();
         for (key, value) in raw_attributes {
-            let parsed_value = value.as_object().unwrap();
-            let size = match parsed_value.get("maxLength") {
-                None => None,
-                Some(size) => Some(size.as_u64().unwrap() as u32)
-            };
-            let type_value = match parsed_value.get("type") {
-                None => return Err(ExpectedError::NoneError(String::from("mysql schema attribute must include type!"))),
-                Some(type_value) => type_value
-            };
-            let (_type, nullable) = match type_value {
-                Value::Array(v) => {
-                    let v_str: Vec<String> = v.iter().map(|it| { String::from(it.as_str().unwrap()) }).collect();
-                    if v_str.len() > 2 {
-                        return Err(ExpectedError::InvalidError(String::from("type array size cannot be bigger than 2!")));
-                    }
-                    if v_str.len() > 1 && v_str.get(1).unwrap() != "null" {
-                        return Err(ExpectedError::InvalidError(String::from("second value of types must be null!")));
-                    }
-                    (v_str.get(0).unwrap().clone(), true)
-                }
-                Value::String(v) => (v.clone(), false),
-                _ => return Err(ExpectedError::TypeError(String::from("type only can be string or array!")))
-            };
-
-            let attribute = Attribute {
-                name: key.clone(),
-                _type,
-                max_length: size,
-                nullable,
-            };
-            attributes.push(attribute);
+            match Attribute::from(key, value, strict_paths) {
+                Ok(attribute) => attributes.push(attribute),
+                Err(err) => return Err(ExpectedError::InvalidError(format!("invalid mysql schema attribute! table={}, attribute={}, error={}", table, key, err))),
+            }
         }
 
-        let uniques = get_array(map, "uniques")?;
-        let indexes = get_array(map, "indexes")?;
-        let create_table = Self::create_table(table.clone(), &attributes, uniques, indexes);
-        let insert_query = Self::insert_query(table.clone(), &attributes);
+        let uniques = Self::keys(get_array(map, "uniques")?);
+        let indexes = Self::keys(get_array(map, "indexes")?);
+        let upsert = get_bool(map, "upsert").unwrap_or(false);
+        if upsert && uniques.is_empty() {
+            return Err(ExpectedError::InvalidError(format!("upsert requires uniques! table={}", table)));
+        }
+        let create_table = Self::create_table(table.clone(), &attributes, &uniques, &indexes);
+        let mut insert_query = Self::insert_query(table.clone(), &attributes);
+        if upsert {
+            insert_query = format!("{} {}", insert_query, Self::on_duplicate_key(&attributes, &uniques));
+        }
 
         Ok(Schema {
             table: table.clone(),
             attributes,
             create_table,
             insert_query,
+            upsert,
+            uniques,
+            indexes,
+            version: Sha256::digest(values.to_string().as_bytes()).iter().map(|byte| { format!("{:02x}", byte) }).collect(),
         })
     }
 
+    fn keys(raw_keys: &Vec<Value>) -> Vec<Vec<String>> {
+        raw_keys.iter().map(|keys| {
+            keys.as_array().unwrap().iter().map(|v| { String::from(v.as_str().unwrap()) }).collect()
+        }).collect()
+    }
+
+    /// column values of a value, converted by the format of each attribute.
+    pub fn row(&self, value: &Map<String, Value>) -> Map<String, Value> {
+        self.attributes.iter().map(|attribute| { (attribute.name.clone(), attribute.value(value)) }).collect()
+    }
+
+    /// attributes of the first unique key, which identify a value. none if not `upsert`.
+    pub fn natural_key(&self) -> Option<Vec<&Attribute>> {
+        if !self.upsert {
+            return None;
+        }
+        let keys = self.uniques.first()?;
+        Some(self.attributes.iter().filter(|attribute| { keys.contains(&attribute.name) }).collect())
+    }
+
 
-    fn create_table(table: String, attributes: &Vec<Attribute>, uniques: &Vec<Value>, indexes: &Vec<Value>) -> String {
+    fn create_table(table: String, attributes: &Vec<Attribute>, uniques: &Vec<Vec<String>>, indexes: &Vec<Vec<String>>) -> String {
         let mut query_line: Vec<String> = Vec::new();
         query_line.push(format!("`{}_id` bigint(20) not null auto_increment", table));
         for attribute in attributes.iter() {
-            let converted_type = convert_type(attribute._type.clone(), attribute.max_length).unwrap();
-            if attribute.max_length.is_none() || converted_type == "text" {
-                query_line.push(format!("`{}` {} {}", attribute.name, converted_type, Self::null_or_not(attribute.nullable)));
-            } else {
-                query_line.push(format!("`{}` {}({}) {}", attribute.name, converted_type, attribute.max_length.unwrap(), Self::null_or_not(attribute.nullable)));
-            }
+            query_line.push(Self::column(attribute));
         }
         query_line.push(format!("PRIMARY KEY (`{}_id`)", table));
 
-        for raw_keys in uniques.iter() {
-            let unique_vec: Vec<String> = raw_keys.as_array().unwrap().iter().map(|v| { String::from(v.as_str().unwrap()) }).collect();
-            let unique_name = format!("{}_{}_unique", table, unique_vec.join("_"));
-            let unique_keys = unique_vec.iter().map(|v| { format!("`{}`", v) }).collect::<Vec<String>>().join(", ");
-            query_line.push(format!("unique key `{}` ({})", unique_name, unique_keys));
+        for keys in uniques.iter() {
+            query_line.push(format!("unique key {}", Self::key(table.as_str(), keys, "unique")));
         }
 
-        for raw_keys in indexes.iter() {
-            let index_vec: Vec<String> = raw_keys.as_array().unwrap().iter().map(|v| { String::from(v.as_str().unwrap()) }).collect();
-            let index_name = format!("{}_{}_index", table, index_vec.join("_"));
-            let index_keys = index_vec.iter().map(|v| { format!("`{}`", v) }).collect::<Vec<String>>().join(", ");
-            query_line.push(format!("key `{}` ({}) using btree", index_name, index_keys));
+        for keys in indexes.iter() {
+            query_line.push(format!("key {} using btree", Self::key(table.as_str(), keys, "index")));
         }
 
         let full_query = query_line.join(", ");
@@ -113,6 +124,57 @@ impl Schema {
         format!("create table `{}` ({}) ENGINE=InnoDB AUTO_INCREMENT=1 DEFAULT CHARSET=utf8mb4", table, full_query)
     }
 
+    /// `alter table` statements adding the columns, unique keys and indexes missing in the existing table.
+    /// changed types and removed attributes are left as they are.
+    pub fn migration(&self, columns: &[String], key_names: &[String]) -> Vec<String> {
+        let mut statements: Vec<String> = Vec::new();
+        for attribute in self.attributes.iter().filter(|attribute| { !columns.contains(&attribute.name) }) {
+            statements.push(format!("alter table `{}` add column {}", self.table, Self::column(attribute)));
+        }
+        for keys in self.uniques.iter().filter(|keys| { !key_names.contains(&Self::key_name(self.table.as_str(), keys, "unique")) }) {
+            statements.push(format!("alter table `{}` add unique key {}", self.table, Self::key(self.table.as_str(), keys, "unique")));
+        }
+        for keys in self.indexes.iter().filter(|keys| { !key_names.contains(&Self::key_name(self.table.as_str(), keys, "index")) }) {
+            statements.push(format!("alter table `{}` add key {} using btree", self.table, Self::key(self.table.as_str(), keys, "index")));
+        }
+        statements
+    }
+
+    /// attributes whose type differs from the existing column, which `migration` does not alter.
+    /// ex) [("height", "varchar(100)", "bigint")] as (column, existing type, type of the schema)
+    pub fn changed_types(&self, column_types: &[(String, String)]) -> Vec<(String, String, String)> {
+        self.attributes.iter().filter_map(|attribute| {
+            let (_, existing) = column_types.iter().find(|(column, _)| { *column == attribute.name })?;
+            if normalize_type(existing) == normalize_type(attribute.column_type.as_str()) {
+                None
+            } else {
+                Some((attribute.name.clone(), existing.clone(), attribute.column_type.clone()))
+            }
+        }).collect()
+    }
+
+    /// ex) "`height` varchar(100) not null", "`fee` decimal(78,0) not null default 0 comment 'fee in wei'"
+    fn column(attribute: &Attribute) -> String {
+        let mut column = format!("`{}` {} {}", attribute.name, attribute.column_type, Self::null_or_not(attribute.nullable));
+        if let Some(default) = attribute.default.as_ref() {
+            column = format!("{} default {}", column, default);
+        }
+        if let Some(description) = attribute.description.as_ref() {
+            column = format!("{} comment {}", column, quote(description));
+        }
+        column
+    }
+
+    /// ex) "`tm_block_height_unique` (`height`)"
+    fn key(table: &str, keys: &Vec<String>, suffix: &str) -> String {
+        let columns = keys.iter().map(|v| { format!("`{}`", v) }).collect::<Vec<String>>().join(", ");
+        format!("`{}` ({})", Self::key_name(table, keys, suffix), columns)
+    }
+
+    fn key_name(table: &str, keys: &Vec<String>, suffix: &str) -> String {
+        format!("{}_{}_{}", table, keys.join("_"), suffix)
+    }
+
     fn insert_query(table: String, attributes: &Vec<Attribute>) -> String {
         let columns: Vec<String> = attributes.iter().map(|attribute| { attribute.clone().name.clone() }).collect();
         let column_names = columns.iter().map(|v| { format!("`{}`", v) }).collect::<Vec<String>>().join(", ");
@@ -120,6 +182,18 @@ impl Schema {
         format!("insert into {} ({}) values ({})", table, column_names, values)
     }
 
+    /// columns of unique keys are left as they are. ex) "on duplicate key update `time`=values(`time`)"
+    fn on_duplicate_key(attributes: &Vec<Attribute>, uniques: &Vec<Vec<String>>) -> String {
+        let mut updates: Vec<String> = attributes.iter()
+            .filter(|attribute| { !uniques.iter().any(|keys| { keys.contains(&attribute.name) }) })
+            .map(|attribute| { format!("`{}`=values(`{}`)", attribute.name, attribute.name) }).collect();
+        if updates.is_empty() {
+            let name = &uniques[0][0];
+            updates.push(format!("`{}`=`{}`", name, name));
+        }
+        format!("on duplicate key update {}", updates.join(", "))
+    }
+
     fn null_or_not(nullable: bool) -> String {
         if nullable {
             String::from("null")
@@ -129,4 +203,258 @@ impl Schema {
     }
 }
 
+impl Attribute {
+    fn from(key: &str, value: &Value, strict_paths: bool) -> Result<Attribute, ExpectedError> {
+        let parsed_value = match value.as_object() {
+            Some(parsed_value) => parsed_value,
+            None => return Err(ExpectedError::TypeError(String::from("attribute must be an object!"))),
+        };
+        let type_value = match parsed_value.get("type") {
+            None => return Err(ExpectedError::NoneError(String::from("mysql schema attribute must include type!"))),
+            Some(type_value) => type_value
+        };
+        let (_type, nullable) = match type_value {
+            Value::Array(v) => {
+                if v.iter().any(|it| { !it.is_string() }) {
+                    return Err(ExpectedError::TypeError(String::from("type array must include strings only!")));
+                }
+                let v_str: Vec<String> = v.iter().map(|it| { String::from(it.as_str().unwrap()) }).collect();
+                if v_str.is_empty() || v_str.len() > 2 {
+                    return Err(ExpectedError::InvalidError(String::from("type array size must be 1 or 2!")));
+                }
+                if v_str.len() > 1 && v_str.get(1).unwrap() != "null" {
+                    return Err(ExpectedError::InvalidError(String::from("second value of types must be null!")));
+                }
+                (v_str.get(0).unwrap().clone(), true)
+            }
+            Value::String(v) => (v.clone(), false),
+            _ => return Err(ExpectedError::TypeError(String::from("type only can be string or array!")))
+        };
+        let column_type = column_type(_type.as_str(), parsed_value)?;
+        let format = match parsed_value.get("format") {
+            None => None,
+            Some(_) => Format::find(get_str(parsed_value, "format")?),
+        };
+
+        let path = match (parsed_value.get("path"), parsed_value.get("source")) {
+            (Some(_), Some(_)) => return Err(ExpectedError::InvalidError(String::from("path and source cannot be given together!"))),
+            (Some(_), None) => Path::parse(get_str(parsed_value, "path")?, strict_paths)?,
+            (None, Some(_)) => Path::parse(get_str(parsed_value, "source")?, strict_paths)?,
+            (None, None) => Path::parse(key, strict_paths)?,
+        };
+        let default = match parsed_value.get("default") {
+            None => None,
+            Some(Value::Null) if !nullable => return Err(ExpectedError::InvalidError(String::from("default of not null type cannot be null!"))),
+            Some(Value::Null) => Some(String::from("null")),
+            Some(Value::Bool(b)) => Some(b.to_string()),
+            Some(Value::Number(n)) => Some(n.to_string()),
+            Some(Value::String(v)) => Some(quote(v)),
+            Some(_) => return Err(ExpectedError::TypeError(String::from("default must be a string, number, boolean or null!"))),
+        };
+        if default.is_some() && (_type == "object" || _type == "array" || column_type == "text") {
+            return Err(ExpectedError::InvalidError(format!("{} type cannot have default!", _type)));
+        }
+        let description = match parsed_value.get("description") {
+            None => None,
+            Some(_) => Some(String::from(get_str(parsed_value, "description")?)),
+        };
+
+        Ok(Attribute {
+            name: String::from(key),
+            path,
+            column_type,
+            nullable,
+            format,
+            default,
+            description,
+        })
+    }
+
+    /// value of the path, converted by the format. ex) "0x0de0b6b3a7640000" of uint256 -> "1000000000000000000"
+    pub fn value(&self, values: &Map<String, Value>) -> Value {
+        let value = self.path.value(values);
+        match self.format {
+            Some(Format::DateTime) => datetime(&value),
+            Some(Format::Uint256) => uint256(&value),
+            None => value,
+        }
+    }
+}
+
+/// column type as the information schema reports it, without the display width of integers.
+/// ex) "int(11) unsigned" -> "int unsigned", "boolean" -> "tinyint", "json" -> "longtext"
+fn normalize_type(column_type: &str) -> String {
+    let lowered = column_type.trim().to_lowercase();
+    let lowered = match lowered.as_str() {
+        "boolean" | "bool" => String::from("tinyint(1)"),
+        "json" => String::from("longtext"),
+        _ => lowered,
+    };
+    for integer in ["tinyint", "smallint", "mediumint", "int", "bigint"].iter() {
+        if lowered.starts_with(format!("{}(", integer).as_str()) {
+            if let Some(end) = lowered.find(')') {
+                return format!("{}{}", integer, &lowered[end + 1..]);
+            }
+        }
+    }
+    lowered
+}
+
+/// quoted sql string literal. ex) it's -> 'it''s'
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''"))
+}
+
 enumeration!(Order; {Asc: "asc"}, {Desc: "desc"});
+enumeration!(Format; {DateTime: "date-time"}, {Uint256: "uint256"});
+
+#[cfg(test)]
+mod mysql_test {
+    use serde_json::{json, Value};
+
+    use crate::types::mysql::Schema;
+
+    #[test]
+    fn schema_upsert_test() {
+        let values = json!({
+            "attributes": {
+                "height": {"type": "string", "maxLength": 100},
+                "time": {"type": ["string", "null"], "maxLength": 100}
+            },
+            "uniques": [["height"]],
+            "indexes": [],
+            "upsert": true
+        });
+        let schema = Schema::from(String::from("tm_block"), &values).unwrap();
+        assert_eq!(schema.insert_query, "insert into tm_block (`height`, `time`) values (?, ?) on duplicate key update `time`=values(`time`)");
+        let natural_key: Vec<&str> = schema.natural_key().unwrap().iter().map(|attribute| { attribute.name.as_str() }).collect();
+        assert_eq!(natural_key, vec!["height"]);
+    }
+
+    #[test]
+    fn schema_upsert_without_uniques_test() {
+        let values = json!({
+            "attributes": {
+                "height": {"type": "string", "maxLength": 100}
+            },
+            "uniques": [],
+            "indexes": [],
+            "upsert": true
+        });
+        assert!(Schema::from(String::from("tm_block"), &values).is_err());
+    }
+
+    #[test]
+    fn schema_migration_test() {
+        let values = json!({
+            "attributes": {
+                "height": {"type": "string", "maxLength": 100},
+                "time": {"type": ["string", "null"], "maxLength": 100}
+            },
+            "uniques": [["height"]],
+            "indexes": [["time"]]
+        });
+        let schema = Schema::from(String::from("tm_block"), &values).unwrap();
+        assert_eq!(schema.version.len(), 64);
+        assert_eq!(schema.create_table, "create table `tm_block` (`tm_block_id` bigint(20) not null auto_increment, `height` varchar(100) not null, `time` varchar(100) null, PRIMARY KEY (`tm_block_id`), unique key `tm_block_height_unique` (`height`), key `tm_block_time_index` (`time`) using btree) ENGINE=InnoDB AUTO_INCREMENT=1 DEFAULT CHARSET=utf8mb4");
+
+        let columns = vec![String::from("tm_block_id"), String::from("height")];
+        let key_names = vec![String::from("PRIMARY"), String::from("tm_block_height_unique")];
+        assert_eq!(schema.migration(&columns, &key_names), vec![
+            "alter table `tm_block` add column `time` varchar(100) null",
+            "alter table `tm_block` add key `tm_block_time_index` (`time`) using btree",
+        ]);
+        let columns = vec![String::from("tm_block_id"), String::from("height"), String::from("time")];
+        let key_names = vec![String::from("PRIMARY"), String::from("tm_block_height_unique"), String::from("tm_block_time_index")];
+        assert!(schema.migration(&columns, &key_names).is_empty());
+    }
+
+    #[test]
+    fn schema_changed_types_test() {
+        let values = json!({
+            "attributes": {
+                "height": {"type": "integer", "minimum": 0, "maximum": 4294967295u64},
+                "flag": {"type": "boolean"},
+                "raw": {"type": "object"},
+                "hash": {"type": "string", "maxLength": 100}
+            },
+            "uniques": [],
+            "indexes": []
+        });
+        let schema = Schema::from(String::from("tm_block"), &values).unwrap();
+        let column_types = vec![
+            (String::from("height"), String::from("int(10) unsigned")),
+            (String::from("flag"), String::from("tinyint(1)")),
+            (String::from("raw"), String::from("longtext")),
+            (String::from("hash"), String::from("varchar(64)")),
+        ];
+        assert_eq!(schema.changed_types(&column_types), vec![(String::from("hash"), String::from("varchar(64)"), String::from("varchar(100)"))]);
+    }
+
+    #[test]
+    fn schema_insert_test() {
+        let values = json!({
+            "attributes": {
+                "height": {"type": "string", "maxLength": 100}
+            },
+            "uniques": [["height"]],
+            "indexes": []
+        });
+        let schema = Schema::from(String::from("tm_block"), &values).unwrap();
+        assert_eq!(schema.insert_query, "insert into tm_block (`height`) values (?)");
+        assert!(schema.natural_key().is_none());
+    }
+
+    #[test]
+    fn schema_format_test() {
+        let values = json!({
+            "attributes": {
+                "number": {"type": "string", "format": "uint256", "source": "header.number"},
+                "timestamp": {"type": "string", "format": "date-time", "path": "header.timestamp"}
+            },
+            "uniques": [],
+            "indexes": []
+        });
+        let schema = Schema::from(String::from("eth_block"), &values).unwrap();
+        assert_eq!(schema.create_table, "create table `eth_block` (`eth_block_id` bigint(20) not null auto_increment, `number` decimal(78,0) not null, `timestamp` datetime(6) not null, PRIMARY KEY (`eth_block_id`)) ENGINE=InnoDB AUTO_INCREMENT=1 DEFAULT CHARSET=utf8mb4");
+
+        let value = json!({"header": {"number": "0xff", "timestamp": "2021-08-01T09:54:48Z"}});
+        assert_eq!(Value::Object(schema.row(value.as_object().unwrap())), json!({"number": "255", "timestamp": "2021-08-01 09:54:48"}));
+    }
+
+    #[test]
+    fn schema_default_and_description_test() {
+        let values = json!({
+            "attributes": {
+                "fee": {"type": "integer", "minimum": 0, "maximum": 255, "default": 0, "description": "fee in gwei"},
+                "memo": {"type": ["string", "null"], "maxLength": 100, "default": null, "description": "memo's text"},
+                "amount": {"type": "number", "multipleOf": 0.001, "default": 1.5},
+                "denom": {"type": "string", "maxLength": 10, "default": "uatom"}
+            },
+            "uniques": [],
+            "indexes": []
+        });
+        let schema = Schema::from(String::from("tx"), &values).unwrap();
+        assert_eq!(schema.create_table, "create table `tx` (`tx_id` bigint(20) not null auto_increment, `fee` tinyint unsigned not null default 0 comment 'fee in gwei', `memo` varchar(100) null default null comment 'memo''s text', `amount` decimal(65,3) not null default 1.5, `denom` varchar(10) not null default 'uatom', PRIMARY KEY (`tx_id`)) ENGINE=InnoDB AUTO_INCREMENT=1 DEFAULT CHARSET=utf8mb4");
+    }
+
+    #[test]
+    fn schema_invalid_attribute_test() {
+        let invalid = vec![
+            (json!({"type": "integer", "format": "date-time"}), "date-time format requires string type!"),
+            (json!({"type": "string", "format": "email"}), "unsupported format!"),
+            (json!({"type": "string", "path": "a", "source": "b"}), "path and source cannot be given together!"),
+            (json!({"type": "string", "maxLength": 10, "default": null}), "default of not null type cannot be null!"),
+            (json!({"type": "string", "maxLength": 10, "default": {}}), "default must be a string, number, boolean or null!"),
+            (json!({"type": "object", "default": "{}"}), "object type cannot have default!"),
+            (json!({"type": "string", "maxLength": 10, "description": 1}), "description"),
+            (json!({"type": "uuid"}), "unsupported type! type=uuid"),
+        ];
+        for (attribute, message) in invalid {
+            let values = json!({"attributes": {"hash": attribute}, "uniques": [], "indexes": []});
+            let err = Schema::from(String::from("tx"), &values).unwrap_err().to_string();
+            assert!(err.contains("table=tx, attribute=hash"), "{}", err);
+            assert!(err.contains(message), "{}", err);
+        }
+    }
+}
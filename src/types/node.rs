@@ -0,0 +1,284 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::enumeration;
+use crate::libs::node::{has_secret, redact_url, REDACTED};
+use crate::types::enumeration::Enumeration;
+
+/// failed requests in a row double the retry delay of a node up to 2^MAX_RETRY_SHIFT times.
+const MAX_RETRY_SHIFT: u32 = 6;
+/// newest request counts 1/SAMPLE_WEIGHT of the moving averages.
+const SAMPLE_WEIGHT: u64 = 5;
+
+enumeration!(NodeSelection; {Best: "best"}, {RoundRobin: "round_robin"});
+
+/// health of a node measured by the requests of a subscription.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct NodeHealth {
+    /// moving average of request latency in milliseconds.
+    pub latency: u64,
+    /// moving average of failed requests in percent.
+    pub error_rate: u64,
+    pub requests: u64,
+    /// failed requests in a row.
+    pub failures: u32,
+    /// unix timestamp in milliseconds the node is retried after.
+    pub retry_at: u64,
+    /// latest height the node reported. 0 if unknown.
+    pub head_height: u64,
+}
+
+impl NodeHealth {
+    pub fn record_success(&mut self, latency: u64) {
+        self.record(latency, false);
+        self.failures = 0;
+        self.retry_at = 0;
+    }
+
+    /// the node is skipped for `retry_interval`, doubled on every failure in a row.
+    pub fn record_failure(&mut self, latency: u64, retry_interval: u64, now: u64) {
+        self.record(latency, true);
+        self.retry_at = now + (retry_interval << self.failures.min(MAX_RETRY_SHIFT));
+        self.failures += 1;
+    }
+
+    pub fn record_head(&mut self, head_height: u64) {
+        self.head_height = self.head_height.max(head_height);
+    }
+
+    fn record(&mut self, latency: u64, failed: bool) {
+        let failed = if failed { 100 } else { 0 };
+        if self.requests == 0 {
+            self.latency = latency;
+            self.error_rate = failed;
+        } else {
+            self.latency = (self.latency * (SAMPLE_WEIGHT - 1) + latency) / SAMPLE_WEIGHT;
+            self.error_rate = (self.error_rate * (SAMPLE_WEIGHT - 1) + failed) / SAMPLE_WEIGHT;
+        }
+        self.requests += 1;
+    }
+
+    pub fn is_available(&self, now: u64) -> bool {
+        self.retry_at <= now
+    }
+
+    /// lower is better. failing nodes look slower, and unused nodes come first to be measured.
+    pub fn score(&self) -> u64 {
+        self.latency * (100 + 10 * self.error_rate)
+    }
+}
+
+/// node of the registry. subscriptions refer to it by `name`, so its url and credentials are not stored in tasks.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Endpoint {
+    pub name: String,
+    pub url: String,
+    /// sent with every request to the node. ex) {"x-api-key": "..."}
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub basic_auth: Option<BasicAuth>,
+    /// requests per second to the node, shared by every subscription. 0 is unlimited.
+    #[serde(default)]
+    pub rate_limit: u64,
+    /// nodes of a subscription must serve the same chain. ex) "cosmoshub-4"
+    #[serde(default)]
+    pub chain_id: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct BasicAuth {
+    pub username: String,
+    pub password: String,
+}
+
+impl Endpoint {
+    /// `headers` with the authorization header of `basic_auth`.
+    pub fn auth_headers(&self) -> Vec<(String, String)> {
+        let mut headers: Vec<(String, String)> = self.headers.iter().map(|(name, value)| { (name.clone(), value.clone()) }).collect();
+        if let Some(basic_auth) = self.basic_auth.as_ref() {
+            let credentials = base64::encode(format!("{}:{}", basic_auth.username, basic_auth.password));
+            headers.push((String::from("Authorization"), format!("Basic {}", credentials)));
+        }
+        headers
+    }
+
+    /// true if the endpoint has headers, `basic_auth` or a url with secrets, which are stored redacted.
+    pub fn has_credentials(&self) -> bool {
+        !self.headers.is_empty() || self.basic_auth.is_some() || has_secret(self.url.as_str()) || self.url.contains(REDACTED)
+    }
+
+    /// takes the url and credentials of the same node in the registry file, as a stored endpoint has them redacted.
+    pub fn restore_credentials(&mut self, registered: &Endpoint) {
+        self.url = registered.url.clone();
+        self.headers = registered.headers.clone();
+        self.basic_auth = registered.basic_auth.clone();
+    }
+
+    pub fn redacted(&self) -> Value {
+        let mut endpoint = json!(self);
+        Self::redact(endpoint.as_object_mut().unwrap());
+        endpoint
+    }
+
+    /// hides header values, the password and secrets of the url in a stored endpoint.
+    pub fn redact(endpoint: &mut Map<String, Value>) {
+        if let Some(Value::String(url)) = endpoint.get("url") {
+            let url = redact_url(url.as_str());
+            endpoint.insert(String::from("url"), Value::String(url));
+        }
+        if let Some(Value::Object(headers)) = endpoint.get_mut("headers") {
+            for value in headers.values_mut() {
+                *value = json!(REDACTED);
+            }
+        }
+        if let Some(Value::Object(basic_auth)) = endpoint.get_mut("basic_auth") {
+            basic_auth.insert(String::from("password"), json!(REDACTED));
+        }
+    }
+}
+
+/// rate limit of requests per second, bursting up to a second of requests.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    rate: u64,
+    /// tokens in thousandths. it goes below zero by the requests waiting for a token.
+    tokens: i64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: u64, now: Instant) -> Self {
+        TokenBucket { rate, tokens: (rate * 1000) as i64, updated_at: now }
+    }
+
+    /// takes a token and returns how long to wait for it. waiting requests borrow tokens ahead, so they keep their order.
+    pub fn take(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_micros() as u64;
+        let refilled = (elapsed * self.rate / 1000) as i64;
+        self.tokens = (self.tokens + refilled).min((self.rate * 1000) as i64) - 1000;
+        self.updated_at = now;
+        if self.tokens >= 0 {
+            Duration::from_millis(0)
+        } else {
+            Duration::from_micros((-self.tokens) as u64 * 1000 / self.rate)
+        }
+    }
+}
+
+/// HTTP requests to a node since start, shown by `node_metrics`.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct RequestMetrics {
+    pub requests: u64,
+    /// requests failed with an error or a status other than success, including the retried ones.
+    pub failures: u64,
+    pub retries: u64,
+    /// responses of 429 Too Many Requests.
+    pub throttled: u64,
+    /// average latency in milliseconds.
+    pub latency: u64,
+    pub max_latency: u64,
+}
+
+impl RequestMetrics {
+    pub fn record(&mut self, latency: u64, failed: bool, throttled: bool) {
+        self.latency = (self.latency * self.requests + latency) / (self.requests + 1);
+        self.max_latency = self.max_latency.max(latency);
+        self.requests += 1;
+        if failed {
+            self.failures += 1;
+        }
+        if throttled {
+            self.throttled += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod node_test {
+    use std::time::{Duration, Instant};
+
+    use serde_json::json;
+
+    use crate::types::node::{Endpoint, NodeHealth, RequestMetrics, TokenBucket};
+
+    #[test]
+    fn node_health_retry_test() {
+        let mut health = NodeHealth::default();
+        health.record_failure(100, 1000, 0);
+        assert_eq!(health.retry_at, 1000);
+        health.record_failure(100, 1000, 1000);
+        assert_eq!(health.retry_at, 3000);
+        assert!(!health.is_available(2999));
+        assert!(health.is_available(3000));
+
+        health.record_success(50);
+        assert_eq!((health.failures, health.retry_at), (0, 0));
+        assert_eq!(health.latency, 90);
+        assert_eq!(health.error_rate, 80);
+    }
+
+    #[test]
+    fn node_health_score_test() {
+        let mut fast_failing = NodeHealth::default();
+        fast_failing.record_failure(100, 1000, 0);
+        let mut slow = NodeHealth::default();
+        slow.record_success(500);
+        assert!(slow.score() < fast_failing.score());
+        assert_eq!(NodeHealth::default().score(), 0);
+    }
+
+    #[test]
+    fn endpoint_redacted_test() {
+        let endpoint: Endpoint = serde_json::from_value(json!({
+            "name": "infura",
+            "url": "https://mainnet.infura.io/v3/0123456789abcdef0123456789abcdef",
+            "headers": {"x-api-key": "secret"},
+            "basic_auth": {"username": "user", "password": "pass"},
+            "chain_id": "1"
+        })).unwrap();
+        assert_eq!(endpoint.auth_headers(), vec![
+            (String::from("x-api-key"), String::from("secret")),
+            (String::from("Authorization"), String::from("Basic dXNlcjpwYXNz")),
+        ]);
+        assert_eq!(endpoint.redacted(), json!({
+            "name": "infura",
+            "url": "https://mainnet.infura.io/v3/***",
+            "headers": {"x-api-key": "***"},
+            "basic_auth": {"username": "user", "password": "***"},
+            "rate_limit": 0,
+            "chain_id": "1"
+        }));
+
+        let mut stored: Endpoint = serde_json::from_value(endpoint.redacted()).unwrap();
+        assert!(stored.has_credentials());
+        stored.restore_credentials(&endpoint);
+        assert_eq!(stored, endpoint);
+
+        let mut public = Endpoint::default();
+        public.url = String::from("https://api.cosmos.network");
+        assert!(!public.has_credentials());
+    }
+
+    #[test]
+    fn token_bucket_test() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2, now);
+        assert_eq!(bucket.take(now), Duration::from_millis(0));
+        assert_eq!(bucket.take(now), Duration::from_millis(0));
+        assert_eq!(bucket.take(now), Duration::from_millis(500));
+        assert_eq!(bucket.take(now), Duration::from_millis(1000));
+        assert_eq!(bucket.take(now + Duration::from_secs(10)), Duration::from_millis(0));
+    }
+
+    #[test]
+    fn request_metrics_test() {
+        let mut metrics = RequestMetrics::default();
+        metrics.record(100, false, false);
+        metrics.record(300, true, true);
+        assert_eq!(metrics, RequestMetrics { requests: 2, failures: 1, retries: 0, throttled: 1, latency: 200, max_latency: 300 });
+    }
+}
@@ -7,3 +7,8 @@ pub mod get_blocks;
 pub mod get_txs;
 pub mod resubscribe;
 pub mod stop_subscribe;
+pub mod eth_subscribe;
+pub mod validate_filter;
+pub mod tm_subscribe;
+pub mod node_add;
+pub mod node_remove;
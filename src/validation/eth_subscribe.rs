@@ -0,0 +1,98 @@
+use serde_json::{Map, Value};
+
+use crate::error::error::ExpectedError;
+
+pub fn verify(params: &Map<String, Value>) -> Result<(), ExpectedError> {
+    if let Some(address) = params.get("address") {
+        let valid = match address {
+            Value::String(address) => is_hex(address),
+            Value::Array(addresses) => addresses.iter().all(|address| { address.as_str().map_or(false, is_hex) }),
+            _ => false,
+        };
+        if !valid {
+            return Err(ExpectedError::TypeError(String::from("address must be hex string or array of hex string!")));
+        }
+    }
+    if let Some(topics) = params.get("topics") {
+        let valid = match topics {
+            Value::Array(topics) => topics.len() <= 4 && topics.iter().all(|topic| {
+                match topic {
+                    Value::Null => true,
+                    Value::String(topic) => is_hex(topic),
+                    Value::Array(topic_or) => topic_or.iter().all(|topic| { topic.as_str().map_or(false, is_hex) }),
+                    _ => false,
+                }
+            }),
+            _ => false,
+        };
+        if !valid {
+            return Err(ExpectedError::TypeError(String::from("topics must be array of null, hex string or array of hex string! max_size=4")));
+        }
+    }
+    if params.get("with_receipts").is_some() && !params.get("with_receipts").unwrap().is_boolean() {
+        return Err(ExpectedError::TypeError(String::from("with_receipts is not bool!")));
+    }
+    if let Some(block_tag) = params.get("block_tag") {
+        if !["latest", "safe", "finalized"].contains(&block_tag.as_str().unwrap_or("")) {
+            return Err(ExpectedError::InvalidError(String::from("block_tag must be one of [latest, safe, finalized]!")));
+        }
+    }
+    Ok(())
+}
+
+fn is_hex(value: &str) -> bool {
+    value.starts_with("0x") && value[2..].chars().all(|c| { c.is_ascii_hexdigit() })
+}
+
+#[cfg(test)]
+mod eth_subscribe_test {
+    use serde_json::{json, Map};
+
+    use crate::validation::eth_subscribe::verify;
+
+    #[test]
+    fn verify_test_success() {
+        let mut params = Map::new();
+        params.insert(String::from("address"), json!("0xdac17f958d2ee523a2206206994597c13d831ec7"));
+        params.insert(String::from("topics"), json!(["0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef", null, ["0x000000000000000000000000dac17f958d2ee523a2206206994597c13d831ec7"]]));
+        let result = verify(&params);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn verify_test_address_error() {
+        let mut params = Map::new();
+        params.insert(String::from("address"), json!(["0xdac17f958d2ee523a2206206994597c13d831ec7", "dac17f"]));
+        let result = verify(&params);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn verify_test_with_receipts_error() {
+        let mut params = Map::new();
+        params.insert(String::from("with_receipts"), json!("true"));
+        let result = verify(&params);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn verify_test_block_tag_error() {
+        let mut params = Map::new();
+        params.insert(String::from("block_tag"), json!("pending"));
+        let result = verify(&params);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn verify_test_topics_error() {
+        let mut params = Map::new();
+        params.insert(String::from("topics"), json!("0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"));
+        let result = verify(&params);
+
+        assert!(result.is_err());
+    }
+}
@@ -2,9 +2,14 @@ use serde_json::{Map, Value};
 
 use crate::validation::verify::verify_default;
 use crate::error::error::ExpectedError;
+use crate::libs::node::SECRET_PREFIX;
 
+/// credentials of nodes are not readable.
 pub fn verify(params: &Map<String, Value>) -> Result<(), ExpectedError> {
     verify_default(params, vec![("key", "string")])?;
+    if params.get("key").unwrap().as_str().unwrap().starts_with(SECRET_PREFIX) {
+        return Err(ExpectedError::InvalidError(String::from("credentials of nodes are not readable!")));
+    }
     Ok(())
 }
 
@@ -23,6 +28,15 @@ mod find_by_key_test {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn verify_test_secret_key_error() {
+        let mut params = Map::new();
+        params.insert(String::from("key"), json!("node_secret:cosmos"));
+        let result = verify(&params);
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn verify_test_type_error() {
         let mut params = Map::new();
@@ -0,0 +1,103 @@
+use reqwest::header::{HeaderName, HeaderValue};
+use serde_json::{Map, Value};
+
+use crate::error::error::ExpectedError;
+use crate::libs::node;
+use crate::validation::verify::verify_default;
+
+const SCHEMES: [&str; 4] = ["http://", "https://", "ws://", "wss://"];
+
+pub fn verify(params: &Map<String, Value>) -> Result<(), ExpectedError> {
+    verify_default(params, vec![("name", "string"), ("url", "string")])?;
+    let name = params.get("name").unwrap().as_str().unwrap();
+    if name.is_empty() || !node::is_name(name) {
+        return Err(ExpectedError::InvalidError(String::from("name must not be empty or a url!")));
+    }
+    let url = params.get("url").unwrap().as_str().unwrap();
+    if !SCHEMES.iter().any(|scheme| { url.starts_with(scheme) }) {
+        return Err(ExpectedError::InvalidError(String::from("url must be http://, https://, ws:// or wss:// url!")));
+    }
+    if let Some(headers) = params.get("headers") {
+        let headers = headers.as_object().ok_or_else(|| { ExpectedError::TypeError(String::from("headers is not object!")) })?;
+        for (name, value) in headers.iter() {
+            let value = value.as_str().unwrap_or("");
+            if HeaderName::from_bytes(name.as_bytes()).is_err() || HeaderValue::from_str(value).is_err() || value.is_empty() {
+                return Err(ExpectedError::InvalidError(format!("header is invalid! header={}", name)));
+            }
+        }
+    }
+    if let Some(basic_auth) = params.get("basic_auth") {
+        let basic_auth = basic_auth.as_object().ok_or_else(|| { ExpectedError::TypeError(String::from("basic_auth is not object!")) })?;
+        verify_default(basic_auth, vec![("username", "string"), ("password", "string")])?;
+    }
+    if params.get("rate_limit").is_some() && !params.get("rate_limit").unwrap().is_u64() {
+        return Err(ExpectedError::TypeError(String::from("rate_limit is not u64!")));
+    }
+    if params.get("chain_id").is_some() && !params.get("chain_id").unwrap().is_string() {
+        return Err(ExpectedError::TypeError(String::from("chain_id is not string!")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod node_add_test {
+    use serde_json::{json, Map, Value};
+
+    use crate::validation::node_add::verify;
+
+    fn params() -> Map<String, Value> {
+        let mut params = Map::new();
+        params.insert(String::from("name"), json!("infura"));
+        params.insert(String::from("url"), json!("https://mainnet.infura.io/v3/..."));
+        params
+    }
+
+    #[test]
+    fn verify_test_success() {
+        let mut params = params();
+        params.insert(String::from("headers"), json!({"x-api-key": "..."}));
+        params.insert(String::from("basic_auth"), json!({"username": "user", "password": "..."}));
+        params.insert(String::from("rate_limit"), json!(10));
+        params.insert(String::from("chain_id"), json!("1"));
+        let result = verify(&params);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn verify_test_name_error() {
+        let mut params = params();
+        params.insert(String::from("name"), json!("https://mainnet.infura.io"));
+        let result = verify(&params);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn verify_test_url_error() {
+        let mut params = params();
+        params.insert(String::from("url"), json!("mainnet.infura.io"));
+        let result = verify(&params);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn verify_test_headers_error() {
+        let mut params = params();
+        params.insert(String::from("headers"), json!({"x api key": "..."}));
+        assert!(verify(&params).is_err());
+
+        params.insert(String::from("headers"), json!({"x-api-key": 1}));
+        assert!(verify(&params).is_err());
+    }
+
+    #[test]
+    fn verify_test_basic_auth_error() {
+        let mut params = params();
+        params.insert(String::from("basic_auth"), json!({"username": "user"}));
+        let result = verify(&params);
+
+        assert!(result.is_err());
+    }
+}
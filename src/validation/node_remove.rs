@@ -0,0 +1,33 @@
+use serde_json::{Map, Value};
+
+use crate::error::error::ExpectedError;
+use crate::validation::verify::verify_default;
+
+pub fn verify(params: &Map<String, Value>) -> Result<(), ExpectedError> {
+    verify_default(params, vec![("name", "string")])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod node_remove_test {
+    use serde_json::{json, Map};
+
+    use crate::validation::node_remove::verify;
+
+    #[test]
+    fn verify_test_success() {
+        let mut params = Map::new();
+        params.insert(String::from("name"), json!("infura"));
+        let result = verify(&params);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn verify_test_value_none() {
+        let params = Map::new();
+        let result = verify(&params);
+
+        assert!(result.is_err());
+    }
+}
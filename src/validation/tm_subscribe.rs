@@ -0,0 +1,99 @@
+use serde_json::{Map, Value};
+
+use crate::error::error::ExpectedError;
+
+pub fn verify(params: &Map<String, Value>) -> Result<(), ExpectedError> {
+    if let Some(events) = params.get("events") {
+        if params.get("target").and_then(|target| { target.as_str() }) != Some("tx") {
+            return Err(ExpectedError::InvalidError(String::from("events is only supported on tx target!")));
+        }
+        let valid = match events {
+            Value::Array(events) => events.iter().all(|event| { event.as_str().map_or(false, is_event) }),
+            _ => false,
+        };
+        if !valid {
+            return Err(ExpectedError::TypeError(String::from("events must be array of event query string! example=\"message.action='/cosmos.bank.v1beta1.MsgSend'\"")));
+        }
+    }
+    if let Some(backend) = params.get("backend") {
+        if !["lcd", "rpc"].contains(&backend.as_str().unwrap_or("")) {
+            return Err(ExpectedError::InvalidError(String::from("backend must be one of [lcd, rpc]!")));
+        }
+        if backend == "lcd" && params.get("target").and_then(|target| { target.as_str() }) == Some("block_events") {
+            return Err(ExpectedError::InvalidError(String::from("block_events target is only supported on rpc backend!")));
+        }
+    }
+    Ok(())
+}
+
+/// `{type}.{attribute}={value}`, and the value can be quoted.
+fn is_event(event: &str) -> bool {
+    let (key, value) = match event.split_once('=') {
+        Some(key_value) => key_value,
+        None => return false,
+    };
+    let key_valid = match key.rsplit_once('.') {
+        Some((event_type, attribute)) => !event_type.is_empty() && !attribute.is_empty()
+            && key.chars().all(|c| { c.is_ascii_alphanumeric() || "_-.".contains(c) }),
+        None => false,
+    };
+    let value = value.trim_matches('\'');
+    key_valid && !value.is_empty() && !value.contains(|c: char| { c == '\'' || c.is_whitespace() })
+}
+
+#[cfg(test)]
+mod tm_subscribe_test {
+    use serde_json::{json, Map};
+
+    use crate::validation::tm_subscribe::verify;
+
+    #[test]
+    fn verify_test_success() {
+        let mut params = Map::new();
+        params.insert(String::from("target"), json!("tx"));
+        params.insert(String::from("events"), json!(["message.action='/cosmos.bank.v1beta1.MsgSend'", "transfer.recipient=cosmos1abc"]));
+        let result = verify(&params);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn verify_test_events_error() {
+        let mut params = Map::new();
+        params.insert(String::from("target"), json!("tx"));
+        params.insert(String::from("events"), json!(["recipient=cosmos1abc"]));
+        let result = verify(&params);
+
+        assert!(result.is_err());
+
+        params.insert(String::from("events"), json!("transfer.recipient=cosmos1abc"));
+        let result = verify(&params);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn verify_test_backend_error() {
+        let mut params = Map::new();
+        params.insert(String::from("target"), json!("block"));
+        params.insert(String::from("backend"), json!("rpc"));
+        assert!(verify(&params).is_ok());
+
+        params.insert(String::from("backend"), json!("grpc"));
+        assert!(verify(&params).is_err());
+
+        params.insert(String::from("target"), json!("block_events"));
+        params.insert(String::from("backend"), json!("lcd"));
+        assert!(verify(&params).is_err());
+    }
+
+    #[test]
+    fn verify_test_target_error() {
+        let mut params = Map::new();
+        params.insert(String::from("target"), json!("block"));
+        params.insert(String::from("events"), json!(["transfer.recipient=cosmos1abc"]));
+        let result = verify(&params);
+
+        assert!(result.is_err());
+    }
+}
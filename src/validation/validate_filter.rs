@@ -0,0 +1,55 @@
+use serde_json::{Map, Value};
+
+use crate::error::error::ExpectedError;
+use crate::libs::filter::Filter;
+use crate::validation::verify::verify_default;
+
+pub fn verify(params: &Map<String, Value>) -> Result<(), ExpectedError> {
+    verify_default(params, vec![
+        ("filter", "string"),
+        ("sample", "object"),
+    ])?;
+    if params.get("strict_paths").is_some() && !params.get("strict_paths").unwrap().is_boolean() {
+        return Err(ExpectedError::TypeError(String::from("strict_paths is not bool!")));
+    }
+    let strict_paths = params.get("strict_paths").map_or(false, |strict_paths| { strict_paths.as_bool().unwrap() });
+    Filter::parse(params.get("filter").unwrap().as_str().unwrap(), strict_paths)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod validate_filter_test {
+    use serde_json::{json, Map};
+
+    use crate::validation::validate_filter::verify;
+
+    #[test]
+    fn verify_test_success() {
+        let mut params = Map::new();
+        params.insert(String::from("filter"), json!("status=0x1 & value>0x0"));
+        params.insert(String::from("sample"), json!({"status": "0x1", "value": "0x1"}));
+        let result = verify(&params);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn verify_test_filter_error() {
+        let mut params = Map::new();
+        params.insert(String::from("filter"), json!("(status=0x1"));
+        params.insert(String::from("sample"), json!({"status": "0x1"}));
+        let result = verify(&params);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn verify_test_type_error() {
+        let mut params = Map::new();
+        params.insert(String::from("filter"), json!("status=0x1"));
+        params.insert(String::from("sample"), json!("0x1"));
+        let result = verify(&params);
+
+        assert!(result.is_err());
+    }
+}